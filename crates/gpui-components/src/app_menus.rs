@@ -2,7 +2,7 @@ use gpui::{App, Menu, MenuItem, SharedString};
 use gpui_component::{ThemeMode, ThemeRegistry};
 
 use crate::{
-    About, CloseWindow, Open, Quit, SelectLocale, ToggleSearch,
+    About, CloseWindow, Open, Quit, Save, SaveAs, SelectLocale, ToggleLayoutInModel, ToggleSearch,
     themes::{SwitchTheme, SwitchThemeMode},
 };
 
@@ -14,6 +14,10 @@ pub fn init(title: impl Into<SharedString>, cx: &mut App) {
                 MenuItem::action("About", About),
                 MenuItem::Separator,
                 MenuItem::action("Open...", Open),
+                MenuItem::action("Save", Save),
+                MenuItem::action("Save As...", SaveAs),
+                MenuItem::Separator,
+                MenuItem::action("Write Layout to Model", ToggleLayoutInModel),
                 MenuItem::Separator,
                 MenuItem::Submenu(Menu {
                     name: "Appearance".into(),
@@ -19,7 +19,7 @@ use gpui::{
     Action, AnyElement, AnyView, App, AppContext, Bounds, Context, Div, Entity, EventEmitter,
     Focusable, Global, Hsla, InteractiveElement, IntoElement, KeyBinding, ParentElement, Pixels,
     Render, RenderOnce, SharedString, Size, StyleRefinement, Styled, Window, WindowBounds,
-    WindowKind, WindowOptions, actions, div, prelude::FluentBuilder as _, px, rems, size,
+    WindowId, WindowKind, WindowOptions, actions, div, prelude::FluentBuilder as _, px, rems, size,
 };
 
 pub use image_story::ImageStory;
@@ -28,6 +28,7 @@ pub use otp_input_story::OtpInputStory;
 pub use popover_story::PopoverStory;
 pub use select_story::SelectStory;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 pub use textarea_story::TextareaStory;
 pub use title_bar::AppTitleBar;
 pub use tooltip_story::TooltipStory;
@@ -68,6 +69,9 @@ actions!(
     [
         About,
         Open,
+        Save,
+        SaveAs,
+        ToggleLayoutInModel,
         Quit,
         CloseWindow,
         ToggleSearch,
@@ -82,11 +86,14 @@ const PANEL_NAME: &str = "StoryContainer";
 
 pub struct AppState {
     pub invisible_panels: Entity<Vec<SharedString>>,
+    /// Windows with unsaved changes, marked in their title bar
+    pub edited_windows: Entity<HashSet<WindowId>>,
 }
 impl AppState {
     fn init(cx: &mut App) {
         let state = Self {
             invisible_panels: cx.new(|_| Vec::new()),
+            edited_windows: cx.new(|_| HashSet::new()),
         };
         cx.set_global::<AppState>(state);
     }
@@ -100,6 +107,23 @@ impl AppState {
     }
 }
 
+/// Mark the window as having unsaved changes, or as saved
+pub fn set_window_edited(edited: bool, window: &mut Window, cx: &mut App) {
+    let id = window.window_handle().window_id();
+    window.set_window_edited(edited);
+    let edited_windows = AppState::global(cx).edited_windows.clone();
+    edited_windows.update(cx, |windows, cx| {
+        let changed = if edited {
+            windows.insert(id)
+        } else {
+            windows.remove(&id)
+        };
+        if changed {
+            cx.notify();
+        }
+    });
+}
+
 pub fn create_new_window<F, E>(title: &str, crate_view_fn: F, cx: &mut App)
 where
     E: Into<AnyView>,
@@ -245,6 +269,14 @@ pub fn init(cx: &mut App) {
         #[cfg(not(target_os = "macos"))]
         KeyBinding::new("ctrl-o", Open, None),
         #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-s", Save, None),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-s", Save, None),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-shift-s", SaveAs, None),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-shift-s", SaveAs, None),
+        #[cfg(target_os = "macos")]
         KeyBinding::new("cmd-q", Quit, None),
         #[cfg(not(target_os = "macos"))]
         KeyBinding::new("alt-f4", Quit, None),
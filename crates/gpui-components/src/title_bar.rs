@@ -3,7 +3,7 @@ use std::rc::Rc;
 use gpui::{
     AnyElement, App, AppContext, Context, Corner, Entity, FocusHandle, InteractiveElement as _,
     IntoElement, MouseButton, ParentElement as _, Render, SharedString, Styled as _, Subscription,
-    Window, div, px,
+    Window, div, prelude::FluentBuilder as _, px,
 };
 use gpui_component::{
     ActiveTheme as _, IconName, PixelsExt, Side, Sizable as _, Theme, TitleBar, WindowExt as _,
@@ -13,7 +13,7 @@ use gpui_component::{
     scroll::ScrollbarShow,
 };
 
-use crate::{SelectFont, SelectRadius, SelectScrollbarShow, app_menus};
+use crate::{AppState, SelectFont, SelectRadius, SelectScrollbarShow, app_menus};
 
 pub struct AppTitleBar {
     app_menu_bar: Entity<AppMenuBar>,
@@ -32,12 +32,13 @@ impl AppTitleBar {
 
         let font_size_selector = cx.new(|cx| FontSizeSelector::new(window, cx));
         let app_menu_bar = AppMenuBar::new(window, cx);
+        let edited_windows = AppState::global(cx).edited_windows.clone();
 
         Self {
             app_menu_bar,
             font_size_selector,
             child: Rc::new(|_, _| div().into_any_element()),
-            _subscriptions: vec![],
+            _subscriptions: vec![cx.observe(&edited_windows, |_, _, cx| cx.notify())],
         }
     }
 
@@ -54,10 +55,28 @@ impl AppTitleBar {
 impl Render for AppTitleBar {
     fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         let notifications_count = window.notifications(cx).len();
+        let edited = AppState::global(cx)
+            .edited_windows
+            .read(cx)
+            .contains(&window.window_handle().window_id());
 
         TitleBar::new()
             // left side
-            .child(div().flex().items_center().child(self.app_menu_bar.clone()))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap_2()
+                    .child(self.app_menu_bar.clone())
+                    .when(edited, |this| {
+                        this.child(
+                            div()
+                                .text_xs()
+                                .text_color(cx.theme().muted_foreground)
+                                .child("● Unsaved changes"),
+                        )
+                    }),
+            )
             .child(
                 div()
                     .flex()
@@ -43,6 +43,7 @@ impl Render for LayoutExample {
 
 fn main() {
     Application::new().run(|cx: &mut App| {
+        graphview::init(cx);
         let mut window_opts = WindowOptions::default();
         window_opts.app_id = Some("GPUG Kitchen Sink".to_string());
 
@@ -0,0 +1,249 @@
+//! Bus layout: buses as horizontal bars above a row of the ECUs that tap into them
+//!
+//! Every bus gets a row of its own. The ECUs connected to a bus are ordered so that the
+//! buses' taps stay close together, which keeps the drops from one bus from crossing the
+//! bars of the others. Each bar reaches from the middle of its first tap to the middle of
+//! its last one, and a little beyond.
+
+use crate::layout_run::{LayoutInput, LayoutOutput};
+
+/// Left and top margin of the layout
+const MARGIN: f32 = 50.0;
+/// Vertical distance between the bars of two buses
+const BUS_ROW: f32 = 40.0;
+/// Gap between the lowest bar and the tapping ECUs, and between rows of nodes
+const ROW_GAP: f32 = 60.0;
+/// Gap between neighbouring nodes of a row
+const NODE_GAP: f32 = 40.0;
+/// Rounds of reordering the ECUs by the position of their buses
+const SWEEPS: usize = 8;
+/// How far a bar reaches beyond the middle of its outermost taps
+pub(crate) const BAR_OVERHANG: f32 = 40.0;
+
+pub(crate) fn layout(input: &LayoutInput) -> LayoutOutput {
+    let n = input.nodes.len();
+    let buses: Vec<usize> = (0..n).filter(|&i| input.nodes[i].bus).collect();
+
+    // ECUs tapping each bus, whichever way their edges point
+    let mut taps: Vec<Vec<usize>> = vec![Vec::new(); n];
+    for &(_, source, target) in &input.edges {
+        let (bus, tap) = match (input.nodes[source].bus, input.nodes[target].bus) {
+            (true, false) => (source, target),
+            (false, true) => (target, source),
+            _ => continue,
+        };
+        if !taps[bus].contains(&tap) {
+            taps[bus].push(tap);
+        }
+    }
+    let mut tapping: Vec<usize> = (0..n)
+        .filter(|&i| buses.iter().any(|&bus| taps[bus].contains(&i)))
+        .collect();
+
+    // Barycenter sweeps: a bus sits in the middle of its ECUs, an ECU in the middle of its
+    // buses. The order with the fewest crossings wins.
+    let mut best = (crossings(&tapping, &buses, &taps), tapping.clone());
+    for _ in 0..SWEEPS {
+        let slot = slots(&tapping, n);
+        let bus_center: Vec<(usize, f32)> = buses
+            .iter()
+            .map(|&bus| (bus, mean(taps[bus].iter().map(|&tap| slot[tap]))))
+            .collect();
+        let key = |tap: usize| {
+            mean(
+                bus_center
+                    .iter()
+                    .filter(|(bus, _)| taps[*bus].contains(&tap))
+                    .map(|&(_, center)| center),
+            )
+        };
+        tapping.sort_by(|&a, &b| key(a).total_cmp(&key(b)));
+        let count = crossings(&tapping, &buses, &taps);
+        if count < best.0 {
+            best = (count, tapping.clone());
+        }
+    }
+    let tapping = best.1;
+    let rows = bus_rows(&tapping, &buses, &taps);
+
+    let mut positions: Vec<(f32, f32)> = input.nodes.iter().map(|node| (node.x, node.y)).collect();
+    for (row, &bus) in rows.iter().enumerate() {
+        positions[bus].1 = MARGIN + row as f32 * BUS_ROW;
+    }
+
+    // The tapping ECUs in a row below the bars, everything else in a row below them
+    let tap_y = MARGIN + rows.len().saturating_sub(1) as f32 * BUS_ROW + ROW_GAP;
+    let tap_height = place_row(&tapping, tap_y, input, &mut positions);
+    let others: Vec<usize> = (0..n)
+        .filter(|i| !input.nodes[*i].bus && !tapping.contains(i))
+        .collect();
+    place_row(&others, tap_y + tap_height + ROW_GAP, input, &mut positions);
+
+    // Bars span their taps; one nothing taps keeps the size of its box
+    let mut bars = Vec::with_capacity(buses.len());
+    for &bus in &buses {
+        let centers = taps[bus]
+            .iter()
+            .map(|&tap| positions[tap].0 + input.nodes[tap].width / 2.0);
+        let (x, width) = bar_span(centers).unwrap_or((MARGIN, 0.0));
+        positions[bus].0 = x;
+        bars.push((bus, width));
+    }
+
+    LayoutOutput {
+        positions: Some(positions),
+        paths: Vec::new(),
+        bars,
+    }
+}
+
+/// Left end and width of a bar over taps with their middles at `centers`
+pub(crate) fn bar_span(centers: impl Iterator<Item = f32>) -> Option<(f32, f32)> {
+    let (lo, hi) = centers.fold(None, |span: Option<(f32, f32)>, x| match span {
+        Some((lo, hi)) => Some((lo.min(x), hi.max(x))),
+        None => Some((x, x)),
+    })?;
+    Some((lo - BAR_OVERHANG, hi - lo + 2.0 * BAR_OVERHANG))
+}
+
+/// Place nodes side by side at `y`; returns the height of the row
+fn place_row(row: &[usize], y: f32, input: &LayoutInput, positions: &mut [(f32, f32)]) -> f32 {
+    let mut x = MARGIN;
+    let mut height = 0.0f32;
+    for &i in row {
+        positions[i] = (x, y);
+        x += input.nodes[i].width + NODE_GAP;
+        height = height.max(input.nodes[i].height);
+    }
+    height
+}
+
+/// Position of each node in the ECU order
+fn slots(order: &[usize], n: usize) -> Vec<f32> {
+    let mut slot = vec![0.0; n];
+    for (position, &i) in order.iter().enumerate() {
+        slot[i] = position as f32;
+    }
+    slot
+}
+
+fn mean(values: impl Iterator<Item = f32>) -> f32 {
+    let (sum, count) = values.fold((0.0, 0), |(sum, count), v| (sum + v, count + 1));
+    if count == 0 { 0.0 } else { sum / count as f32 }
+}
+
+/// First and last slot of the ECUs tapping a bus
+fn span(bus: usize, slot: &[f32], taps: &[Vec<usize>]) -> (f32, f32) {
+    taps[bus]
+        .iter()
+        .map(|&tap| slot[tap])
+        .fold((f32::MAX, f32::MIN), |(lo, hi), s| (lo.min(s), hi.max(s)))
+}
+
+/// Buses from the top row down: the widest on top, so the narrow ones lower down are
+/// crossed by as few drops as possible
+fn bus_rows(order: &[usize], buses: &[usize], taps: &[Vec<usize>]) -> Vec<usize> {
+    let slot = slots(order, taps.len());
+    let width = |bus: usize| {
+        let (lo, hi) = span(bus, &slot, taps);
+        if taps[bus].is_empty() { 0.0 } else { hi - lo }
+    };
+    let mut rows = buses.to_vec();
+    rows.sort_by(|&a, &b| width(b).total_cmp(&width(a)));
+    rows
+}
+
+/// Number of bars the drops pass through on their way down to an ECU not tapping them
+fn crossings(order: &[usize], buses: &[usize], taps: &[Vec<usize>]) -> usize {
+    let slot = slots(order, taps.len());
+    let rows = bus_rows(order, buses, taps);
+    let mut count = 0;
+    for (row, &bus) in rows.iter().enumerate() {
+        for &tap in &taps[bus] {
+            count += rows[row + 1..]
+                .iter()
+                .filter(|&&below| !taps[below].contains(&tap))
+                .filter(|&&below| {
+                    let (lo, hi) = span(below, &slot, taps);
+                    lo <= slot[tap] && slot[tap] <= hi
+                })
+                .count();
+        }
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout_run::LayoutNode;
+
+    /// Buses first, then ECUs of 100x40; `taps` connects ECUs to buses by index
+    fn input(buses: usize, ecus: usize, taps: &[(usize, usize)]) -> LayoutInput {
+        let node = |i: usize| LayoutNode {
+            name: format!("node{}", i),
+            width: 100.0,
+            height: 40.0,
+            x: 0.0,
+            y: 0.0,
+            bus: i < buses,
+        };
+        LayoutInput {
+            nodes: (0..buses + ecus).map(node).collect(),
+            edges: taps
+                .iter()
+                .enumerate()
+                .map(|(i, &(ecu, bus))| (i, ecu, bus))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_layout() {
+        // CAN (0) is tapped by the first and third ECU, ETH (1) by the second and fourth
+        let input = input(2, 4, &[(2, 0), (3, 1), (4, 0), (5, 1)]);
+        let output = layout(&input);
+        let positions = output.positions.unwrap();
+
+        // The sweeps move the ECUs of each bus next to each other
+        let mut order: Vec<usize> = (2..6).collect();
+        order.sort_by(|&a, &b| positions[a].0.total_cmp(&positions[b].0));
+        assert_eq!(order, [2, 4, 3, 5]);
+        assert_eq!(positions[2], (50.0, 150.0));
+        assert_eq!(positions[4], (190.0, 150.0));
+
+        // One row per bus, each bar reaching a little beyond its outermost taps
+        assert_eq!(positions[0], (60.0, 50.0));
+        assert_eq!(positions[1], (340.0, 90.0));
+        assert_eq!(output.bars, [(0, 220.0), (1, 220.0)]);
+        assert_eq!(crossings(&[2, 4, 3, 5], &[0, 1], &taps(&input)), 0);
+        assert_eq!(crossings(&[2, 3, 4, 5], &[0, 1], &taps(&input)), 1);
+    }
+
+    #[test]
+    fn test_untapped_bus() {
+        let input = input(2, 1, &[(2, 0)]);
+        let output = layout(&input);
+        let positions = output.positions.unwrap();
+        assert_eq!(positions[1].0, MARGIN);
+        assert_eq!(output.bars, [(0, 80.0), (1, 0.0)]);
+    }
+
+    #[test]
+    fn test_bar_span() {
+        assert_eq!(bar_span([].into_iter()), None);
+        assert_eq!(
+            bar_span([300.0, 100.0, 200.0].into_iter()),
+            Some((60.0, 280.0))
+        );
+    }
+
+    /// ECUs tapping each bus, as the layout collects them
+    fn taps(input: &LayoutInput) -> Vec<Vec<usize>> {
+        let mut taps = vec![Vec::new(); input.nodes.len()];
+        for &(_, ecu, bus) in &input.edges {
+            taps[bus].push(ecu);
+        }
+        taps
+    }
+}
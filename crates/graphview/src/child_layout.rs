@@ -0,0 +1,171 @@
+//! Deterministic layout of the nested children inside a node
+//!
+//! Size estimation, rendering and hit-testing all use the same layout, so they agree at any
+//! nesting depth. Coordinates are unzoomed pixels relative to the parent element.
+
+use crate::node::NodeChild;
+
+/// Height of the node header (type badge and name)
+pub const HEADER_HEIGHT: f32 = 28.0;
+/// Inner padding of nodes and child boxes
+pub const PADDING: f32 = 4.0;
+/// Space between sibling boxes
+const GAP: f32 = 4.0;
+/// Kind label and name line at the top of a child box
+pub const LABEL_HEIGHT: f32 = 26.0;
+/// One row of port markers
+pub const PORT_ROW_HEIGHT: f32 = 12.0;
+const MEMBER_LINE_HEIGHT: f32 = 16.0;
+const FIELD_LINE_HEIGHT: f32 = 14.0;
+const MIN_BOX_WIDTH: f32 = 60.0;
+
+/// Position and size of a child element and its descendants
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ChildLayout {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub children: Vec<ChildLayout>,
+}
+
+impl ChildLayout {
+    fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.x && x <= self.x + self.width && y >= self.y && y <= self.y + self.height
+    }
+
+    /// Index path to the innermost element containing the point (relative to the parent)
+    pub fn hit(layouts: &[ChildLayout], x: f32, y: f32) -> Option<Vec<usize>> {
+        let (index, layout) = layouts
+            .iter()
+            .enumerate()
+            .find(|(_, layout)| layout.contains(x, y))?;
+        let mut path = vec![index];
+        if let Some(inner) = Self::hit(&layout.children, x - layout.x, y - layout.y) {
+            path.extend(inner);
+        }
+        Some(path)
+    }
+}
+
+/// Size of a node and the layout of its children below the header
+pub fn layout_node(
+    name: &str,
+    node_type: &str,
+    children: &[NodeChild],
+) -> (f32, f32, Vec<ChildLayout>) {
+    let base_width = 120.0f32;
+    let char_width = 7.2f32;
+    let padding = 24.0f32;
+
+    // Width from name and type
+    let name_width = name.len() as f32 * char_width + padding;
+    let type_width = node_type.len() as f32 * 6.0 + 40.0;
+    let header_width = name_width.max(type_width).max(base_width);
+
+    if children.is_empty() {
+        return (header_width, HEADER_HEIGHT, Vec::new());
+    }
+
+    let (mut layouts, grid_width, grid_height) = arrange(children, PADDING, HEADER_HEIGHT);
+    let width = header_width.max(grid_width + 2.0 * PADDING);
+    stretch(&mut layouts, width - 2.0 * PADDING);
+
+    (width, HEADER_HEIGHT + grid_height + 2.0 * PADDING, layouts)
+}
+
+/// Layout of a child element and its descendants, positioned at the origin
+fn layout_child(child: &NodeChild) -> ChildLayout {
+    if child.is_member() {
+        let line_width = |text: &str, indent: f32| text.len() as f32 * 6.0 + 16.0 + indent;
+        let width = child
+            .children
+            .iter()
+            .map(|field| line_width(&field.name, 12.0))
+            .fold(line_width(&child.name, 0.0), f32::max);
+        return ChildLayout {
+            width,
+            height: MEMBER_LINE_HEIGHT + child.children.len() as f32 * FIELD_LINE_HEIGHT,
+            ..Default::default()
+        };
+    }
+
+    let label_width =
+        ((child.kind.len() + 2) as f32 * 5.5).max(child.name.len() as f32 * 6.0) + 2.0 * PADDING;
+
+    // Incoming and outgoing ports are listed side by side, one port per row
+    let (ports_width, port_rows) = {
+        let side = |incoming: bool| {
+            child
+                .ports
+                .iter()
+                .filter(move |p| p.kind.is_incoming() == incoming)
+        };
+        let longest =
+            |incoming: bool| side(incoming).map(|p| p.name.len()).max().unwrap_or(0) as f32;
+        let width = (longest(true) + longest(false)) * 5.0 + 40.0;
+        (width, side(true).count().max(side(false).count()))
+    };
+
+    let top = PADDING + LABEL_HEIGHT + port_rows as f32 * PORT_ROW_HEIGHT;
+    // Collapsed containers shrink to their label
+    let (children, grid_width, grid_height) = arrange(child.visible_children(), PADDING, top);
+    let grid_height = if children.is_empty() {
+        0.0
+    } else {
+        grid_height + GAP
+    };
+
+    ChildLayout {
+        x: 0.0,
+        y: 0.0,
+        width: label_width
+            .max(ports_width)
+            .max(grid_width + 2.0 * PADDING)
+            .max(MIN_BOX_WIDTH),
+        height: top + grid_height + PADDING,
+        children,
+    }
+}
+
+/// Arrange children in a grid starting at (left, top) and return it with its total size.
+/// Leaf boxes are placed two per row, anything with nested content is stacked.
+fn arrange(children: &[NodeChild], left: f32, top: f32) -> (Vec<ChildLayout>, f32, f32) {
+    let mut layouts: Vec<ChildLayout> = children.iter().map(layout_child).collect();
+    if layouts.is_empty() {
+        return (layouts, 0.0, 0.0);
+    }
+
+    let members = children.iter().any(NodeChild::is_member);
+    let leaves = children
+        .iter()
+        .all(|c| c.visible_children().is_empty() && !c.is_member());
+    let columns = if leaves && children.len() > 1 { 2 } else { 1 };
+    let gap = if members { 0.0 } else { GAP };
+    let cell_width = layouts.iter().map(|l| l.width).fold(0.0, f32::max);
+
+    let mut y = top;
+    for row in layouts.chunks_mut(columns) {
+        let row_height = row.iter().map(|l| l.height).fold(0.0, f32::max);
+        for (column, layout) in row.iter_mut().enumerate() {
+            layout.x = left + column as f32 * (cell_width + gap);
+            layout.y = y;
+            layout.width = cell_width;
+            layout.height = row_height;
+        }
+        y += row_height + gap;
+    }
+
+    let width = columns as f32 * cell_width + (columns - 1) as f32 * gap;
+    (layouts, width, y - gap - top)
+}
+
+/// Widen stacked (single-column) children to the available width
+fn stretch(layouts: &mut [ChildLayout], width: f32) {
+    if layouts.windows(2).any(|w| w[0].x != w[1].x) {
+        return;
+    }
+    for layout in layouts {
+        layout.width = layout.width.max(width);
+    }
+}
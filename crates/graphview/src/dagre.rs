@@ -0,0 +1,264 @@
+//! Dagre hierarchical layout and its settings
+
+use crate::layout_run::{LayoutInput, LayoutOutput};
+
+/// Direction the ranks of the Dagre layout follow
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RankDirection {
+    #[default]
+    TopToBottom,
+    BottomToTop,
+    LeftToRight,
+    RightToLeft,
+}
+
+impl RankDirection {
+    pub const ALL: [RankDirection; 4] = [
+        RankDirection::TopToBottom,
+        RankDirection::BottomToTop,
+        RankDirection::LeftToRight,
+        RankDirection::RightToLeft,
+    ];
+
+    /// Short name, as in Graphviz and dagre.js
+    pub fn label(self) -> &'static str {
+        match self {
+            RankDirection::TopToBottom => "TB",
+            RankDirection::BottomToTop => "BT",
+            RankDirection::LeftToRight => "LR",
+            RankDirection::RightToLeft => "RL",
+        }
+    }
+
+    /// Whether the ranks are columns rather than rows
+    pub fn is_horizontal(self) -> bool {
+        matches!(
+            self,
+            RankDirection::LeftToRight | RankDirection::RightToLeft
+        )
+    }
+}
+
+/// Corner the nodes of each rank are pushed towards
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DagreAlign {
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+}
+
+impl DagreAlign {
+    pub const ALL: [DagreAlign; 4] = [
+        DagreAlign::UpLeft,
+        DagreAlign::UpRight,
+        DagreAlign::DownLeft,
+        DagreAlign::DownRight,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DagreAlign::UpLeft => "UL",
+            DagreAlign::UpRight => "UR",
+            DagreAlign::DownLeft => "DL",
+            DagreAlign::DownRight => "DR",
+        }
+    }
+}
+
+/// Algorithm assigning the nodes to ranks
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DagreRanker {
+    #[default]
+    NetworkSimplex,
+    TightTree,
+    LongestPath,
+}
+
+impl DagreRanker {
+    pub const ALL: [DagreRanker; 3] = [
+        DagreRanker::NetworkSimplex,
+        DagreRanker::TightTree,
+        DagreRanker::LongestPath,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DagreRanker::NetworkSimplex => "Network simplex",
+            DagreRanker::TightTree => "Tight tree",
+            DagreRanker::LongestPath => "Longest path",
+        }
+    }
+}
+
+/// Options of the Dagre layout
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DagreSettings {
+    pub rank_direction: RankDirection,
+    /// Gap between neighbouring nodes of a rank
+    pub node_separation: f32,
+    /// Gap between ranks
+    pub rank_separation: f32,
+    /// Gap between neighbouring edges
+    pub edge_separation: f32,
+    /// `None` balances the nodes between the four alignments
+    pub align: Option<DagreAlign>,
+    pub ranker: DagreRanker,
+}
+
+impl Default for DagreSettings {
+    fn default() -> Self {
+        Self {
+            rank_direction: RankDirection::TopToBottom,
+            node_separation: 30.0,
+            rank_separation: 50.0,
+            edge_separation: 10.0,
+            align: None,
+            ranker: DagreRanker::NetworkSimplex,
+        }
+    }
+}
+
+impl DagreSettings {
+    /// Options for dagre-rs. It places nodes as points, so the separations are measured
+    /// from center to center: the largest node's extent is added to the gaps.
+    fn options(&self, max_width: f32, max_height: f32) -> dagre_rs::LayoutOptions {
+        use dagre_rs::{Align, LayoutOptions, RankDir, Ranker};
+
+        let (across, along) = if self.rank_direction.is_horizontal() {
+            (max_height, max_width)
+        } else {
+            (max_width, max_height)
+        };
+        LayoutOptions {
+            rank_dir: match self.rank_direction {
+                RankDirection::TopToBottom => RankDir::TopToBottom,
+                RankDirection::BottomToTop => RankDir::BottomToTop,
+                RankDirection::LeftToRight => RankDir::LeftToRight,
+                RankDirection::RightToLeft => RankDir::RightToLeft,
+            },
+            node_sep: across + self.node_separation,
+            rank_sep: along + self.rank_separation,
+            edge_sep: self.edge_separation,
+            align: self.align.map(|align| match align {
+                DagreAlign::UpLeft => Align::UpLeft,
+                DagreAlign::UpRight => Align::UpRight,
+                DagreAlign::DownLeft => Align::DownLeft,
+                DagreAlign::DownRight => Align::DownRight,
+            }),
+            ranker: match self.ranker {
+                DagreRanker::NetworkSimplex => Ranker::NetworkSimplex,
+                DagreRanker::TightTree => Ranker::TightTree,
+                DagreRanker::LongestPath => Ranker::LongestPath,
+            },
+            ..Default::default()
+        }
+    }
+}
+
+/// Place the nodes and route the edges through Dagre's control points. Each route runs from
+/// the center of its source to the center of its target; the graph swaps the ends for the
+/// live port anchors when drawing.
+pub(crate) fn layout(input: &LayoutInput, settings: &DagreSettings) -> LayoutOutput {
+    use dagre_rs::DagreLayout;
+    use petgraph::Graph as PetGraph;
+
+    let n = input.nodes.len();
+
+    // Get node dimensions for spacing calculation
+    let max_width = input.nodes.iter().map(|n| n.width).fold(0.0f32, f32::max);
+    let max_height = input.nodes.iter().map(|n| n.height).fold(0.0f32, f32::max);
+
+    // Build petgraph from our graph structure
+    let mut pg: PetGraph<usize, ()> = PetGraph::new();
+    let node_indices: Vec<_> = (0..n).map(|i| pg.add_node(i)).collect();
+    let edge_indices: Vec<_> = input
+        .edges
+        .iter()
+        .map(|&(_, source, target)| pg.add_edge(node_indices[source], node_indices[target], ()))
+        .collect();
+
+    let layout = DagreLayout::with_options(settings.options(max_width, max_height));
+    let result = layout.compute(&pg);
+
+    // Check if we got valid positions
+    if result.node_positions.is_empty() {
+        // Dagre returned no positions - fall back to simple grid layout
+        let cols = (n as f32).sqrt().ceil() as usize;
+        let spacing_x = max_width + 50.0;
+        let spacing_y = max_height + 50.0;
+        let positions = (0..n)
+            .map(|i| {
+                let col = i % cols;
+                let row = i / cols;
+                (50.0 + col as f32 * spacing_x, 50.0 + row as f32 * spacing_y)
+            })
+            .collect();
+        return LayoutOutput {
+            positions: Some(positions),
+            paths: Vec::new(),
+            bars: Vec::new(),
+        };
+    }
+
+    // Dagre positions start anywhere, offset them so the minimum is at (50, 50)
+    let positions: Vec<(f32, f32)> = node_indices
+        .iter()
+        .map(|index| {
+            result
+                .node_positions
+                .get(index)
+                .copied()
+                .unwrap_or((0.0, 0.0))
+        })
+        .collect();
+    let min_x = positions.iter().map(|p| p.0).fold(f32::MAX, f32::min);
+    let min_y = positions.iter().map(|p| p.1).fold(f32::MAX, f32::min);
+    let (dx, dy) = (50.0 - min_x, 50.0 - min_y);
+    let positions: Vec<(f32, f32)> = positions
+        .into_iter()
+        .map(|(x, y)| (x + dx, y + dy))
+        .collect();
+
+    // Dagre places points where the nodes' top left corners go. Bends lie in the slots of
+    // dummy nodes or between the ranks, so they are moved to the middle of a slot.
+    let slot = |(x, y): (f32, f32)| (x + max_width / 2.0, y + max_height / 2.0);
+    let center = |i: usize| {
+        let (x, y) = positions[i];
+        (
+            x + input.nodes[i].width / 2.0,
+            y + input.nodes[i].height / 2.0,
+        )
+    };
+    let paths = input
+        .edges
+        .iter()
+        .zip(&edge_indices)
+        .map(|(&(i, source, target), index)| {
+            let ends = (slot(positions[source]), slot(positions[target]));
+            let bends = result
+                .edge_points
+                .get(index)
+                .into_iter()
+                .flatten()
+                .map(|&(x, y)| slot((x + dx, y + dy)))
+                // Dagre starts and ends its routes at the nodes, those ends are replaced
+                .filter(|&p| distance(p, ends.0) > 1.0 && distance(p, ends.1) > 1.0);
+            let path = std::iter::once(center(source))
+                .chain(bends)
+                .chain(std::iter::once(center(target)))
+                .collect();
+            (i, path)
+        })
+        .collect();
+
+    LayoutOutput {
+        positions: Some(positions),
+        paths,
+        bars: Vec::new(),
+    }
+}
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    (a.0 - b.0).hypot(a.1 - b.1)
+}
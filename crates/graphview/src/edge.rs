@@ -1,8 +1,35 @@
+use crate::node::ChildPath;
+
+/// Category of an edge, used to style it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum EdgeKind {
+    /// Physical connection, e.g. an ECU attached to a bus
+    #[default]
+    Link,
+    /// Signal data flow from an output port to an input port
+    Signal,
+    /// Service provided by one port and required by another, drawn dashed
+    Service,
+    /// Port referencing an interface definition, drawn dashed
+    Reference,
+}
+
+impl EdgeKind {
+    pub fn is_dashed(&self) -> bool {
+        matches!(self, Self::Service | Self::Reference)
+    }
+}
+
 #[derive(Clone)]
 pub struct GraphEdge {
     pub source: usize,
     pub target: usize,
+    /// Nested child of the source node the edge starts at (`None` for the node itself)
+    pub source_child: Option<ChildPath>,
+    /// Nested child of the target node the edge ends at (`None` for the node itself)
+    pub target_child: Option<ChildPath>,
     pub path: Vec<(f32, f32)>, // Edge waypoints in pixels
+    pub kind: EdgeKind,
 }
 
 impl GraphEdge {
@@ -10,7 +37,32 @@ impl GraphEdge {
         Self {
             source,
             target,
+            source_child: None,
+            target_child: None,
             path: vec![],
+            kind: EdgeKind::default(),
         }
     }
+
+    pub fn with_kind(mut self, kind: EdgeKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Start the edge at a nested child of the source node
+    pub fn from_child(mut self, path: ChildPath) -> Self {
+        self.source_child = Some(path);
+        self
+    }
+
+    /// End the edge at a nested child of the target node
+    pub fn to_child(mut self, path: ChildPath) -> Self {
+        self.target_child = Some(path);
+        self
+    }
+
+    /// Whether the edge attaches to a nested child on either end
+    pub fn has_child_endpoint(&self) -> bool {
+        self.source_child.is_some() || self.target_child.is_some()
+    }
 }
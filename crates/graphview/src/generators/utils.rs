@@ -41,6 +41,7 @@ pub fn generate_nodes(n: usize) -> Vec<GraphNode> {
         let y = px(top + ry * height);
         nodes.push(GraphNode {
             id: (i as u64) + 1,
+            key: format!("node-{}", i + 1),
             name: format!("Node {}", i + 1),
             x,
             y,
@@ -48,12 +49,18 @@ pub fn generate_nodes(n: usize) -> Vec<GraphNode> {
             zoom: 1.0,
             pan: point(px(0.0), px(0.0)),
             selected: false,
+            selected_child: None,
+            collapsed: false,
+            pinned: false,
+            hint: None,
             container_offset: point(px(0.0), px(0.0)),
             width: 80.0,
             height: 32.0,
             node_type: "node".to_string(),
             children: Vec::new(),
+            bar_width: None,
             span: None,
+            details: Vec::new(),
             graph_entity: None,
         });
     }
@@ -1,10 +1,109 @@
 use gpui::prelude::FluentBuilder;
 use gpui::*;
 use gpui::{Context, IntoElement, ParentElement, Render, Styled, Window, canvas, div};
-use gpui_component::ActiveTheme;
+use gpui_component::button::{Button, ButtonVariants};
+use gpui_component::input::{Input, InputEvent, InputState};
+use gpui_component::menu::ContextMenuExt;
+use gpui_component::popover::Popover;
+use gpui_component::{ActiveTheme, Sizable};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use crate::bus::bar_span;
+use crate::dagre::{DagreAlign, DagreRanker, DagreSettings, RankDirection};
+use crate::edge::{EdgeKind, GraphEdge};
+use crate::history::{History, Step};
+use crate::layout_run::{LayoutInput, LayoutNode, LayoutOutput, LayoutRun};
+use crate::node::{ChildPath, GraphNode, NodeChild};
+
+actions!(
+    graph,
+    [
+        ToggleCollapse,
+        TogglePin,
+        AddEcu,
+        AddBus,
+        RenameNode,
+        DeleteNode,
+        Undo,
+        Redo,
+        SelectLeft,
+        SelectRight,
+        SelectUp,
+        SelectDown,
+        FollowEdgeLeft,
+        FollowEdgeRight,
+        FollowEdgeUp,
+        FollowEdgeDown,
+        SelectNext,
+        SelectPrevious,
+        ClearSelection,
+        JumpToSource,
+        ZoomIn,
+        ZoomOut,
+        ResetZoom,
+        FitView
+    ]
+);
+
+const CONTEXT: &str = "Graph";
+
+/// Default duration of the animated move to a new layout
+const LAYOUT_ANIMATION: Duration = Duration::from_millis(400);
+
+/// Zooming with the scroll wheel within this time is undone in one step
+const SCROLL_STEP: Duration = Duration::from_millis(500);
+
+/// Range of the zoom, for the controls, the scroll wheel, fitting and restored layouts
+const MIN_ZOOM: f32 = 0.1;
+const MAX_ZOOM: f32 = 4.0;
+
+/// Register the key bindings of the graph view
+pub fn init(cx: &mut App) {
+    cx.bind_keys([
+        KeyBinding::new("space", ToggleCollapse, Some(CONTEXT)),
+        KeyBinding::new("p", TogglePin, Some(CONTEXT)),
+        KeyBinding::new("e", AddEcu, Some(CONTEXT)),
+        KeyBinding::new("b", AddBus, Some(CONTEXT)),
+        KeyBinding::new("f2", RenameNode, Some(CONTEXT)),
+        KeyBinding::new("delete", DeleteNode, Some(CONTEXT)),
+        KeyBinding::new("backspace", DeleteNode, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-z", Undo, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-z", Undo, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-shift-z", Redo, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-shift-z", Redo, Some(CONTEXT)),
+        KeyBinding::new("left", SelectLeft, Some(CONTEXT)),
+        KeyBinding::new("right", SelectRight, Some(CONTEXT)),
+        KeyBinding::new("up", SelectUp, Some(CONTEXT)),
+        KeyBinding::new("down", SelectDown, Some(CONTEXT)),
+        KeyBinding::new("alt-left", FollowEdgeLeft, Some(CONTEXT)),
+        KeyBinding::new("alt-right", FollowEdgeRight, Some(CONTEXT)),
+        KeyBinding::new("alt-up", FollowEdgeUp, Some(CONTEXT)),
+        KeyBinding::new("alt-down", FollowEdgeDown, Some(CONTEXT)),
+        KeyBinding::new("tab", SelectNext, Some(CONTEXT)),
+        KeyBinding::new("shift-tab", SelectPrevious, Some(CONTEXT)),
+        KeyBinding::new("escape", ClearSelection, Some(CONTEXT)),
+        KeyBinding::new("enter", JumpToSource, Some(CONTEXT)),
+        KeyBinding::new("+", ZoomIn, Some(CONTEXT)),
+        KeyBinding::new("=", ZoomIn, Some(CONTEXT)),
+        KeyBinding::new("-", ZoomOut, Some(CONTEXT)),
+        KeyBinding::new("0", ResetZoom, Some(CONTEXT)),
+        KeyBinding::new("f", FitView, Some(CONTEXT)),
+    ]);
+}
 
-use crate::edge::GraphEdge;
-use crate::node::GraphNode;
+/// Direction of keyboard navigation between nodes
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
 
 /// Edge routing style
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
@@ -14,6 +113,24 @@ pub enum EdgeRouting {
     Straight,
     /// Manhattan-style orthogonal routing (horizontal and vertical segments only)
     Manhattan,
+    /// Smooth curves through the bends of routed edges
+    Spline,
+}
+
+impl EdgeRouting {
+    pub const ALL: [EdgeRouting; 3] = [
+        EdgeRouting::Straight,
+        EdgeRouting::Manhattan,
+        EdgeRouting::Spline,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            EdgeRouting::Straight => "Straight",
+            EdgeRouting::Manhattan => "Orthogonal",
+            EdgeRouting::Spline => "Spline",
+        }
+    }
 }
 
 /// Layout algorithm mode
@@ -26,6 +143,8 @@ pub enum LayoutMode {
     Dagre,
     /// Custom ArciVis layout with orthogonal edge routing
     ArciVis,
+    /// Buses as long bars above the ECUs, which tap into them with vertical drops
+    Bus,
 }
 
 pub struct Graph {
@@ -49,10 +168,69 @@ pub struct Graph {
     pub edge_routing: EdgeRouting,
     /// Layout algorithm mode
     pub layout_mode: LayoutMode,
-    /// Subscriptions to node events
-    node_subscriptions: Vec<Subscription>,
+    /// Options of the Dagre layout
+    pub dagre: DagreSettings,
+    /// Subscriptions to node events, keyed by node entity
+    node_subscriptions: HashMap<EntityId, Subscription>,
     /// Track if nodes are currently being dragged (for ArciVis performance)
     pub is_dragging_nodes: bool,
+    /// Set while the source document is invalid and the graph shows the last valid model
+    pub stale: Option<StaleState>,
+    /// Focus for the graph's key bindings, taken when the graph is clicked
+    focus_handle: FocusHandle,
+    /// Graph position of the last right click, where elements added from the context menu go
+    menu_position: Option<Point<Pixels>>,
+    /// Rename in progress, edited in place
+    renaming: Option<Renaming>,
+    /// Changes to undo and redo
+    history: History,
+    /// Arrangement when the current drag or pan began, recorded for undo once it ends
+    change_start: Option<LayoutSnapshot>,
+    /// Time of the last scroll wheel zoom
+    last_scroll: Option<Instant>,
+    /// Corners of the selection rectangle being dragged out, container-local
+    selection_box: Option<(Point<Pixels>, Point<Pixels>)>,
+    /// Where a node of a multiple selection was pressed; released without a drag, the
+    /// selection narrows to that node
+    pending_select: Option<Point<Pixels>>,
+    /// Layout run in progress on the background executor; dropping it drops its result
+    layout_run: Option<Task<()>>,
+    /// Duration of the animated move of the nodes to a new layout; `None` moves them at once,
+    /// for huge graphs
+    pub layout_animation: Option<Duration>,
+    /// Animated move to a new layout in progress
+    transition: Option<Transition>,
+}
+
+/// Movement of the nodes from their old to their new positions after a layout run
+struct Transition {
+    start: Instant,
+    duration: Duration,
+    /// Node, start and end position
+    moves: Vec<(Entity<GraphNode>, Point<Pixels>, Point<Pixels>)>,
+    /// Routes for the end positions, by index in the edges, applied once the nodes arrive
+    paths: Vec<(usize, Vec<(f32, f32)>)>,
+}
+
+/// Inline editor over the element being renamed
+struct Renaming {
+    span: (usize, usize),
+    nested: bool,
+    name: String,
+    input: Entity<InputState>,
+    /// Graph position and width of the renamed element
+    position: Point<Pixels>,
+    width: f32,
+    _subscription: Subscription,
+}
+
+/// Describes why the displayed graph is out of date with its source document
+#[derive(Clone, Debug)]
+pub struct StaleState {
+    /// First error of the current document
+    pub message: SharedString,
+    /// Byte range of the error in the source document
+    pub span: Option<(usize, usize)>,
 }
 
 /// Event emitted when a node is selected in the graph
@@ -62,13 +240,100 @@ pub struct NodeSelected {
     pub span: Option<(usize, usize)>,
 }
 
+/// Event emitted when several nodes are selected, with the source spans of the selected
+/// elements
+#[derive(Clone, Debug)]
+pub struct SelectionChanged {
+    pub spans: Vec<(usize, usize)>,
+}
+
 #[derive(Clone, Debug)]
 pub struct NodeMoved {
     pub node_id: u64,
 }
 
+/// Event emitted when the graph asks to reveal a range of the source document
+#[derive(Clone, Debug)]
+pub struct JumpToSpan {
+    pub span: (usize, usize),
+}
+
+/// Event emitted when the user edits the model from the graph. The graph does not change by
+/// itself: the owner edits the source document, and the updated model changes the graph.
+#[derive(Clone, Debug)]
+pub enum ModelEdit {
+    /// Add a top-level element of a type ("ecu", "bus") at a graph position
+    Add {
+        node_type: String,
+        position: (f32, f32),
+    },
+    /// Rename the element at `span`; nested elements are named by their first argument
+    Rename {
+        span: (usize, usize),
+        nested: bool,
+        name: String,
+    },
+    /// Remove the elements at the spans
+    Delete { spans: Vec<(usize, usize)> },
+    /// Connect two top-level elements, e.g. an ECU to a bus
+    Connect {
+        source: (usize, usize),
+        target: (usize, usize),
+    },
+    /// Move the nested element at `span` into the container or ECU at `parent`
+    Reparent {
+        span: (usize, usize),
+        parent: (usize, usize),
+    },
+}
+
+/// Event emitted when undo or redo reaches an edit of the model recorded with
+/// [`Graph::push_model_edit`]; the owner reverts or repeats it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ModelHistory {
+    Undo,
+    Redo,
+}
+
+/// Event emitted when the user changed the arrangement of the graph: node positions,
+/// collapsed or pinned nodes or the layout mode
+#[derive(Clone, Debug)]
+pub struct LayoutChanged;
+
+/// Event emitted when only the zoom or pan of the graph changed
+#[derive(Clone, Debug)]
+pub struct ViewChanged;
+
 impl EventEmitter<NodeSelected> for Graph {}
+impl EventEmitter<SelectionChanged> for Graph {}
 impl EventEmitter<NodeMoved> for Graph {}
+impl EventEmitter<JumpToSpan> for Graph {}
+impl EventEmitter<LayoutChanged> for Graph {}
+impl EventEmitter<ViewChanged> for Graph {}
+impl EventEmitter<ModelEdit> for Graph {}
+impl EventEmitter<ModelHistory> for Graph {}
+
+/// The arrangement of a graph, to restore a hand-tuned diagram later
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LayoutSnapshot {
+    /// Node arrangement by node key
+    pub nodes: HashMap<String, NodeLayout>,
+    pub zoom: f32,
+    pub pan: (f32, f32),
+    pub layout_mode: LayoutMode,
+    pub dagre: DagreSettings,
+}
+
+/// Position and state of one node in a [`LayoutSnapshot`]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NodeLayout {
+    pub x: f32,
+    pub y: f32,
+    pub pinned: bool,
+    pub collapsed: bool,
+    /// Name paths of the collapsed nested children
+    pub collapsed_children: Vec<Vec<String>>,
+}
 
 impl Graph {
     pub fn new(
@@ -106,8 +371,22 @@ impl Graph {
             pan_start_pos: point(px(0.0), px(0.0)),
             edge_routing: EdgeRouting::default(),
             layout_mode: LayoutMode::default(),
-            node_subscriptions: Vec::new(),
+            dagre: DagreSettings::default(),
+            node_subscriptions: HashMap::new(),
             is_dragging_nodes: false,
+            stale: None,
+            // Reachable with Tab from the rest of the window, for keyboard-only use
+            focus_handle: cx.focus_handle().tab_stop(true),
+            menu_position: None,
+            renaming: None,
+            history: History::default(),
+            change_start: None,
+            last_scroll: None,
+            selection_box: None,
+            pending_select: None,
+            layout_run: None,
+            layout_animation: Some(LAYOUT_ANIMATION),
+            transition: None,
         }
     }
 
@@ -175,48 +454,245 @@ impl Graph {
         }
     }
 
+    /// Change the options of the Dagre layout, laying out again when the graph uses it
+    pub fn set_dagre_settings(&mut self, settings: DagreSettings, cx: &mut Context<Self>) {
+        if self.dagre == settings {
+            return;
+        }
+        self.dagre = settings;
+        if self.layout_mode == LayoutMode::Dagre {
+            self.apply_dagre_layout(cx);
+        }
+        cx.emit(LayoutChanged);
+        cx.notify();
+    }
+
+    /// Mark the graph as stale (showing the last valid model) or clear the stale state
+    pub fn set_stale(&mut self, stale: Option<StaleState>, cx: &mut Context<Self>) {
+        self.stale = stale;
+        cx.notify();
+    }
+
     /// Update the graph with new nodes and edges
+    ///
+    /// Nodes are matched to the existing ones by their `key`. Matched nodes keep their entity,
+    /// position and selection and only take over the new content; edge routes survive as long
+    /// as both endpoints are unchanged. Only new nodes are created and only vanished ones dropped.
+    /// A node whose layout hint changed moves to the new hint.
     pub fn update_model(
         &mut self,
         nodes: Vec<GraphNode>,
         edges: Vec<GraphEdge>,
         cx: &mut Context<Self>,
     ) {
-        // Create new node entities
-        let mut node_entities: Vec<Entity<GraphNode>> = Vec::with_capacity(nodes.len());
-        let mut subscriptions = Vec::new();
         let graph_entity = cx.entity();
+        let was_empty = self.nodes.is_empty();
+        // A layout of the previous model would not fit anymore
+        self.cancel_layout(cx);
+        self.finish_transition(cx);
+
+        // Remember routed edge paths by their endpoints and kind
+        let old_keys: Vec<String> = self
+            .nodes
+            .iter()
+            .map(|n| cx.read_entity(n, |node, _| node.key.clone()))
+            .collect();
+        let mut old_paths: HashMap<RouteKey, Vec<(f32, f32)>> = HashMap::new();
+        for edge in self.edges.iter().filter(|edge| !edge.path.is_empty()) {
+            if let Some(key) = route_key(&old_keys, edge) {
+                old_paths.insert(key, edge.path.clone());
+            }
+        }
+
+        let mut existing: HashMap<String, Entity<GraphNode>> = old_keys
+            .iter()
+            .cloned()
+            .zip(self.nodes.iter().cloned())
+            .collect();
+        let mut next_id = self
+            .nodes
+            .iter()
+            .map(|n| cx.read_entity(n, |node, _| node.id))
+            .max()
+            .unwrap_or(0)
+            + 1;
+
+        let mut node_entities: Vec<Entity<GraphNode>> = Vec::with_capacity(nodes.len());
+        let mut new_keys: Vec<String> = Vec::with_capacity(nodes.len());
+        // Nodes that were added or changed shape, their edges need to be routed again
+        let mut changed: HashSet<String> = HashSet::new();
 
         for mut node in nodes {
-            node.zoom = self.zoom;
-            node.pan = self.pan;
-            node.container_offset = self.container_offset;
-            node.graph_entity = Some(graph_entity.clone());
-            let node_entity = cx.new(|_| node);
+            new_keys.push(node.key.clone());
+            let node_entity = match existing.remove(&node.key) {
+                Some(node_entity) => {
+                    let graph_entity = graph_entity.clone();
+                    let reshaped = cx.update_entity(&node_entity, move |existing, _| {
+                        let old_size = (existing.width, existing.height);
+                        existing.name = node.name;
+                        existing.node_type = node.node_type;
+                        let mut children = node.children;
+                        NodeChild::keep_collapsed(&existing.children, &mut children);
+                        existing.children = children;
+                        existing.span = node.span;
+                        existing.details = node.details;
+                        // Collapsed nodes keep their reduced size
+                        (existing.width, existing.height) = existing.estimate_dimensions();
+                        existing.graph_entity = Some(graph_entity);
+                        let hinted = node.hint.is_some() && node.hint != existing.hint;
+                        let moved = hinted && (existing.x, existing.y) != (node.x, node.y);
+                        if hinted {
+                            (existing.x, existing.y) = (node.x, node.y);
+                        }
+                        existing.hint = node.hint;
+                        // Only moves and size changes move ports; spans shift on nearly every edit
+                        moved || old_size != (existing.width, existing.height)
+                    });
+                    if reshaped {
+                        changed.insert(node.key.clone());
+                    }
+                    node_entity
+                }
+                None => {
+                    changed.insert(node.key.clone());
+                    node.id = next_id;
+                    next_id += 1;
+                    node.zoom = self.zoom;
+                    node.pan = self.pan;
+                    node.container_offset = self.container_offset;
+                    node.graph_entity = Some(graph_entity.clone());
+                    cx.new(|_| node)
+                }
+            };
 
             // Subscribe to NodeMoved events from this node
-            let subscription = cx.subscribe(
-                &node_entity,
-                |_this: &mut Graph, _node, _event: &NodeMoved, cx| {
-                    // When a node is moved, handle it
-                    cx.notify();
-                },
-            );
-            subscriptions.push(subscription);
+            self.node_subscriptions
+                .entry(node_entity.entity_id())
+                .or_insert_with(|| {
+                    cx.subscribe(
+                        &node_entity,
+                        |_this: &mut Graph, _node, _event: &NodeMoved, cx| {
+                            // When a node is moved, handle it
+                            cx.notify();
+                        },
+                    )
+                });
 
             node_entities.push(node_entity);
         }
 
+        // Drop subscriptions of nodes that are gone
+        let kept: HashSet<EntityId> = node_entities.iter().map(|n| n.entity_id()).collect();
+        self.node_subscriptions.retain(|id, _| kept.contains(id));
+
+        // Carry over routes of edges whose endpoints did not change
+        let mut edges = edges;
+        for edge in &mut edges {
+            let Some(key) = route_key(&new_keys, edge) else {
+                continue;
+            };
+            if edge.path.is_empty()
+                && !changed.contains(key.0)
+                && !changed.contains(key.2)
+                && let Some(path) = old_paths.get(&key)
+            {
+                edge.path = path.clone();
+            }
+        }
+        let needs_routing = edges.iter().any(|e| e.path.is_empty());
+
         self.nodes = node_entities;
         self.edges = edges;
-        self.node_subscriptions = subscriptions;
-        self.needs_layout = true;
+        // Only lay out from scratch when there was nothing to preserve
+        self.needs_layout = was_empty;
+
+        if self.layout_mode == LayoutMode::ArciVis && needs_routing && !was_empty {
+            cx.defer(move |cx| {
+                cx.update_entity(&graph_entity, |graph, cx| {
+                    graph.recalculate_arcivis_edges(cx);
+                });
+            });
+        }
+        // New and removed connections change what the bars span
+        if self.layout_mode == LayoutMode::Bus {
+            self.fit_bus_bars(cx);
+        }
+        cx.notify();
+    }
+
+    /// The current arrangement of the graph
+    pub fn layout_snapshot(&self, cx: &App) -> LayoutSnapshot {
+        let nodes = self
+            .nodes
+            .iter()
+            .map(|n| {
+                let node = n.read(cx);
+                let layout = NodeLayout {
+                    x: node.x / px(1.0),
+                    y: node.y / px(1.0),
+                    pinned: node.pinned,
+                    collapsed: node.collapsed,
+                    collapsed_children: node.collapsed_children(),
+                };
+                (node.key.clone(), layout)
+            })
+            .collect();
+        LayoutSnapshot {
+            nodes,
+            zoom: self.zoom,
+            pan: (self.pan.x / px(1.0), self.pan.y / px(1.0)),
+            layout_mode: self.layout_mode,
+            dagre: self.dagre,
+        }
+    }
+
+    /// Restore an arrangement. Nodes missing from the snapshot keep their position.
+    pub fn restore_layout(&mut self, snapshot: &LayoutSnapshot, cx: &mut Context<Self>) {
+        self.cancel_layout(cx);
+        self.transition = None;
+        self.zoom = snapshot.zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        self.pan = point(px(snapshot.pan.0), px(snapshot.pan.1));
+        self.layout_mode = snapshot.layout_mode;
+        self.dagre = snapshot.dagre;
+        self.playing = false;
+        self.needs_layout = false;
+        self.needs_fit_to_content = false;
+
+        let (zoom, pan) = (self.zoom, self.pan);
+        for n in &self.nodes {
+            cx.update_entity(n, |node, cx| {
+                node.zoom = zoom;
+                node.pan = pan;
+                if let Some(layout) = snapshot.nodes.get(&node.key) {
+                    node.x = px(layout.x);
+                    node.y = px(layout.y);
+                    node.pinned = layout.pinned;
+                    node.collapsed = layout.collapsed && !node.children.is_empty();
+                    node.set_collapsed_children(&layout.collapsed_children);
+                }
+                cx.notify();
+            });
+        }
+
+        // Stored routes belong to the previous positions
+        for edge in &mut self.edges {
+            edge.path.clear();
+        }
+        if self.layout_mode == LayoutMode::ArciVis {
+            self.recalculate_arcivis_edges(cx);
+        }
+        // Bars keep the left ends they had, only their widths are not part of the snapshot
+        if self.layout_mode == LayoutMode::Bus {
+            self.fit_bus_bars(cx);
+        } else {
+            self.apply_bus_bars(&[], cx);
+        }
         cx.notify();
     }
 
     /// Set zoom level and update all nodes
     pub fn set_zoom(&mut self, new_zoom: f32, cx: &mut Context<Self>) {
-        let new_zoom = new_zoom.clamp(0.1, 3.0);
+        let new_zoom = new_zoom.clamp(MIN_ZOOM, MAX_ZOOM);
         if (new_zoom - self.zoom).abs() < 0.001 {
             return;
         }
@@ -227,11 +703,18 @@ impl Graph {
                 node.zoom = zoom;
             });
         }
+        cx.emit(ViewChanged);
         cx.notify();
     }
 
-    /// Handle a node being moved
-    pub fn handle_node_moved(&mut self, _node_id: u64, cx: &mut Context<Self>) {
+    /// Handle a node being moved. The dragged node is being updated and is told apart by its
+    /// entity id; when it is `selected`, the rest of the selection moves along.
+    pub fn handle_node_moved(&mut self, dragged: EntityId, selected: bool, cx: &mut Context<Self>) {
+        self.is_dragging_nodes = true;
+        // A layout finishing now would throw the node away from the cursor, and the other
+        // nodes stop where they are
+        self.cancel_layout(cx);
+        self.transition = None;
         if self.layout_mode == LayoutMode::ArciVis {
             // During dragging, just clear paths to use fast Manhattan routing
             // We'll recalculate ArciVis routes when dragging stops
@@ -239,6 +722,638 @@ impl Graph {
                 edge.path.clear();
             }
             cx.notify();
+        } else if self.layout_mode == LayoutMode::Dagre {
+            // Bends around moved nodes are out of place, their edges run straight between
+            // the ends until the next layout
+            let moved = self.moved_nodes(dragged, selected, cx);
+            let is_moved = |i: usize| moved.get(i) == Some(&true);
+            for edge in &mut self.edges {
+                if edge.path.len() > 2 && (is_moved(edge.source) || is_moved(edge.target)) {
+                    edge.path.drain(1..edge.path.len() - 1);
+                }
+            }
+            cx.notify();
+        } else if self.layout_mode == LayoutMode::Bus {
+            // The bars follow their taps, once the dragged node can be read again
+            let moved: Vec<usize> = self
+                .moved_nodes(dragged, selected, cx)
+                .into_iter()
+                .enumerate()
+                .filter_map(|(i, moved)| moved.then_some(i))
+                .collect();
+            let graph_entity = cx.entity();
+            cx.defer(move |cx| {
+                cx.update_entity(&graph_entity, |graph, cx| {
+                    graph.stretch_bus_bars(&moved, cx);
+                });
+            });
+        }
+    }
+
+    /// Move the other selected nodes along with the dragged one, which is being updated and
+    /// is skipped by its entity id. Moved by hand, they stay where they are put.
+    pub fn move_selected(
+        &mut self,
+        dragged: EntityId,
+        delta: Point<Pixels>,
+        cx: &mut Context<Self>,
+    ) {
+        for n in &self.nodes {
+            if n.entity_id() == dragged {
+                continue;
+            }
+            cx.update_entity(n, |node, cx| {
+                if node.selected {
+                    node.x += delta.x;
+                    node.y += delta.y;
+                    node.pinned = true;
+                    cx.notify();
+                }
+            });
+        }
+    }
+
+    /// Handle a node changing its size (e.g., collapsed or expanded) by laying out again.
+    /// The force simulation picks up the new size by itself.
+    pub fn handle_node_resized(&mut self, cx: &mut Context<Self>) {
+        let graph_entity = cx.entity();
+        match self.layout_mode {
+            LayoutMode::Force => {}
+            LayoutMode::Dagre => cx.defer(move |cx| {
+                cx.update_entity(&graph_entity, |graph, cx| {
+                    graph.apply_dagre_layout(cx);
+                });
+            }),
+            LayoutMode::ArciVis => cx.defer(move |cx| {
+                cx.update_entity(&graph_entity, |graph, cx| {
+                    graph.apply_arcivis_layout(cx);
+                });
+            }),
+            LayoutMode::Bus => cx.defer(move |cx| {
+                cx.update_entity(&graph_entity, |graph, cx| {
+                    graph.apply_bus_layout(cx);
+                });
+            }),
+        }
+        cx.emit(LayoutChanged);
+        cx.notify();
+    }
+
+    /// Collapse or expand the innermost container around `path` in a node
+    pub(crate) fn toggle_collapsed(
+        &mut self,
+        node: &Entity<GraphNode>,
+        path: &[usize],
+        cx: &mut Context<Self>,
+    ) {
+        let toggled = cx.update_entity(node, |node, cx| {
+            let path = node.collapsible_path(path);
+            let toggled = node.toggle_collapsed_at(&path);
+            cx.notify();
+            toggled
+        });
+        if toggled {
+            self.handle_node_resized(cx);
+        }
+    }
+
+    /// Collapse or expand the selected containers
+    fn on_toggle_collapse(
+        &mut self,
+        _: &ToggleCollapse,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        // Selected nodes and containers that can be collapsed or expanded
+        let targets: Vec<(Entity<GraphNode>, ChildPath)> = self
+            .nodes
+            .iter()
+            .filter_map(|node| {
+                let n = node.read(cx);
+                if !n.selected {
+                    return None;
+                }
+                let path = n.collapsible_path(n.selected_child.as_deref().unwrap_or_default());
+                (!path.is_empty() || !n.children.is_empty()).then(|| (node.clone(), path))
+            })
+            .collect();
+        if targets.is_empty() {
+            return;
+        }
+        self.record_layout(cx);
+        for (node, path) in targets {
+            self.toggle_collapsed(&node, &path, cx);
+        }
+    }
+
+    /// Pin the selected nodes in place, or release them if all of them are pinned
+    fn on_toggle_pin(&mut self, _: &TogglePin, _window: &mut Window, cx: &mut Context<Self>) {
+        let selected: Vec<Entity<GraphNode>> = self
+            .nodes
+            .iter()
+            .filter(|n| n.read(cx).selected)
+            .cloned()
+            .collect();
+        if selected.is_empty() {
+            return;
+        }
+        self.record_layout(cx);
+        let pinned = !selected.iter().all(|n| n.read(cx).pinned);
+        for node in &selected {
+            cx.update_entity(node, |node, cx| {
+                node.pinned = pinned;
+                cx.notify();
+            });
+        }
+        cx.emit(LayoutChanged);
+    }
+
+    /// Remember the arrangement before a change by the user, for undo
+    pub fn record_layout(&mut self, cx: &App) {
+        self.history.push(Step::Layout(self.layout_snapshot(cx)));
+        self.last_scroll = None;
+    }
+
+    /// Record an edit of the model made on behalf of the graph, so that undo and redo reach
+    /// it in order with the changes of the arrangement
+    pub fn push_model_edit(&mut self) {
+        self.history.push(Step::Model);
+        self.last_scroll = None;
+    }
+
+    /// Take back an undo or redo of a model edit its owner could not carry out, so the
+    /// graph's history stays in step with the owner's
+    pub fn reject_model_step(&mut self, direction: ModelHistory) {
+        match direction {
+            ModelHistory::Undo => self.history.reject_undo(),
+            ModelHistory::Redo => self.history.reject_redo(),
+        }
+    }
+
+    /// Forget all steps, when a different model is shown
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+        self.change_start = None;
+        self.last_scroll = None;
+    }
+
+    /// Record the drag or pan that just ended, if it changed anything
+    fn finish_change(&mut self, cx: &App) {
+        if let Some(before) = self.change_start.take()
+            && before != self.layout_snapshot(cx)
+        {
+            self.history.push(Step::Layout(before));
+            self.last_scroll = None;
+        }
+    }
+
+    fn on_undo(&mut self, _: &Undo, _window: &mut Window, cx: &mut Context<Self>) {
+        let step = self.history.undo(self.layout_snapshot(cx));
+        self.apply_step(step, ModelHistory::Undo, cx);
+    }
+
+    fn on_redo(&mut self, _: &Redo, _window: &mut Window, cx: &mut Context<Self>) {
+        let step = self.history.redo(self.layout_snapshot(cx));
+        self.apply_step(step, ModelHistory::Redo, cx);
+    }
+
+    fn apply_step(&mut self, step: Option<Step>, direction: ModelHistory, cx: &mut Context<Self>) {
+        match step {
+            Some(Step::Layout(layout)) => {
+                self.restore_layout(&layout, cx);
+                cx.emit(LayoutChanged);
+            }
+            Some(Step::Model) => cx.emit(direction),
+            None => {}
+        }
+    }
+
+    /// Index of the node keyboard navigation starts from: the first selected one
+    fn current_node(&self, cx: &App) -> Option<usize> {
+        self.nodes.iter().position(|n| n.read(cx).selected)
+    }
+
+    fn node_center(&self, index: usize, cx: &App) -> (f32, f32) {
+        let node = self.nodes[index].read(cx);
+        (
+            node.x / px(1.0) + node.width / 2.0,
+            node.y / px(1.0) + node.height / 2.0,
+        )
+    }
+
+    /// Select a node as a whole, alone, and scroll it into view
+    fn select_node(&mut self, index: usize, cx: &mut Context<Self>) {
+        for (i, n) in self.nodes.iter().enumerate() {
+            cx.update_entity(n, |node, _| {
+                node.selected = i == index;
+                node.selected_child = None;
+            });
+        }
+        let node = self.nodes[index].read(cx);
+        let (node_id, span) = (node.id, node.span);
+        cx.emit(NodeSelected { node_id, span });
+        self.scroll_into_view(index, cx);
+        cx.notify();
+    }
+
+    /// Center the view on a node that is not fully visible
+    fn scroll_into_view(&mut self, index: usize, cx: &mut Context<Self>) {
+        let node = self.nodes[index].read(cx);
+        let (width, height) = (px(node.width) * self.zoom, px(node.height) * self.zoom);
+        let left = self.pan.x + node.x * self.zoom;
+        let top = self.pan.y + node.y * self.zoom;
+        let size = self.container_size;
+        if left >= px(0.0)
+            && top >= px(0.0)
+            && left + width <= size.width
+            && top + height <= size.height
+        {
+            return;
+        }
+        self.pan = point(
+            size.width / 2.0 - node.x * self.zoom - width / 2.0,
+            size.height / 2.0 - node.y * self.zoom - height / 2.0,
+        );
+        let pan = self.pan;
+        for n in &self.nodes {
+            cx.update_entity(n, move |node, _| {
+                node.pan = pan;
+            });
+        }
+        cx.emit(ViewChanged);
+    }
+
+    /// Select the nearest node in a direction, among all nodes or only the ones sharing an
+    /// edge with the selected one. Without a selection, navigation starts at the first node.
+    fn select_towards(&mut self, direction: Direction, along_edges: bool, cx: &mut Context<Self>) {
+        let Some(current) = self.current_node(cx) else {
+            if !self.nodes.is_empty() {
+                self.select_node(0, cx);
+            }
+            return;
+        };
+        let candidates: Vec<usize> = if along_edges {
+            self.edges
+                .iter()
+                .filter_map(|edge| match (edge.source, edge.target) {
+                    (source, target) if source == current => Some(target),
+                    (source, target) if target == current => Some(source),
+                    _ => None,
+                })
+                .collect()
+        } else {
+            (0..self.nodes.len()).collect()
+        };
+        let (x, y) = self.node_center(current, cx);
+        let nearest = candidates
+            .into_iter()
+            .filter(|&i| i != current)
+            .filter_map(|i| {
+                let (to_x, to_y) = self.node_center(i, cx);
+                let (dx, dy) = (to_x - x, to_y - y);
+                let (along, across) = match direction {
+                    Direction::Left => (-dx, dy),
+                    Direction::Right => (dx, dy),
+                    Direction::Up => (-dy, dx),
+                    Direction::Down => (dy, dx),
+                };
+                // Nodes off to the side count as farther away than ones straight ahead
+                (along > 0.0).then_some((i, along + 2.0 * across.abs()))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((index, _)) = nearest {
+            self.select_node(index, cx);
+        }
+    }
+
+    fn on_select_left(&mut self, _: &SelectLeft, _window: &mut Window, cx: &mut Context<Self>) {
+        self.select_towards(Direction::Left, false, cx);
+    }
+
+    fn on_select_right(&mut self, _: &SelectRight, _window: &mut Window, cx: &mut Context<Self>) {
+        self.select_towards(Direction::Right, false, cx);
+    }
+
+    fn on_select_up(&mut self, _: &SelectUp, _window: &mut Window, cx: &mut Context<Self>) {
+        self.select_towards(Direction::Up, false, cx);
+    }
+
+    fn on_select_down(&mut self, _: &SelectDown, _window: &mut Window, cx: &mut Context<Self>) {
+        self.select_towards(Direction::Down, false, cx);
+    }
+
+    fn on_follow_edge_left(
+        &mut self,
+        _: &FollowEdgeLeft,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.select_towards(Direction::Left, true, cx);
+    }
+
+    fn on_follow_edge_right(
+        &mut self,
+        _: &FollowEdgeRight,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.select_towards(Direction::Right, true, cx);
+    }
+
+    fn on_follow_edge_up(
+        &mut self,
+        _: &FollowEdgeUp,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.select_towards(Direction::Up, true, cx);
+    }
+
+    fn on_follow_edge_down(
+        &mut self,
+        _: &FollowEdgeDown,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.select_towards(Direction::Down, true, cx);
+    }
+
+    /// Select the nodes one after the other, in model order
+    fn on_select_next(&mut self, _: &SelectNext, _window: &mut Window, cx: &mut Context<Self>) {
+        if self.nodes.is_empty() {
+            return;
+        }
+        let next = self
+            .current_node(cx)
+            .map_or(0, |i| (i + 1) % self.nodes.len());
+        self.select_node(next, cx);
+    }
+
+    fn on_select_previous(
+        &mut self,
+        _: &SelectPrevious,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let count = self.nodes.len();
+        if count == 0 {
+            return;
+        }
+        let previous = self
+            .current_node(cx)
+            .map_or(count - 1, |i| (i + count - 1) % count);
+        self.select_node(previous, cx);
+    }
+
+    fn on_clear_selection(
+        &mut self,
+        _: &ClearSelection,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.selection_box = None;
+        for n in &self.nodes {
+            cx.update_entity(n, |node, _| {
+                node.selected = false;
+                node.selected_child = None;
+            });
+        }
+        cx.notify();
+    }
+
+    /// Reveal the source of the selected element
+    fn on_jump_to_source(
+        &mut self,
+        _: &JumpToSource,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if let Some((_, _, span)) = self.selected_elements(cx).into_iter().next() {
+            cx.emit(JumpToSpan { span });
+        }
+    }
+
+    fn on_zoom_in(&mut self, _: &ZoomIn, _window: &mut Window, cx: &mut Context<Self>) {
+        self.record_layout(cx);
+        self.set_zoom(self.zoom + 0.1, cx);
+    }
+
+    fn on_zoom_out(&mut self, _: &ZoomOut, _window: &mut Window, cx: &mut Context<Self>) {
+        self.record_layout(cx);
+        self.set_zoom(self.zoom - 0.1, cx);
+    }
+
+    fn on_reset_zoom(&mut self, _: &ResetZoom, _window: &mut Window, cx: &mut Context<Self>) {
+        self.record_layout(cx);
+        self.set_zoom(1.0, cx);
+    }
+
+    fn on_fit_view(&mut self, _: &FitView, _window: &mut Window, cx: &mut Context<Self>) {
+        self.record_layout(cx);
+        self.fit_to_content(cx);
+        cx.emit(ViewChanged);
+    }
+
+    /// Index of the topmost node at a container-local position
+    fn node_at(&self, cursor: Point<Pixels>, cx: &App) -> Option<usize> {
+        self.nodes.iter().position(|n| {
+            let node = n.read(cx);
+            let left = self.pan.x + node.x * self.zoom;
+            let top = self.pan.y + node.y * self.zoom;
+            cursor.x >= left
+                && cursor.x <= left + px(node.width) * self.zoom
+                && cursor.y >= top
+                && cursor.y <= top + px(node.height) * self.zoom
+        })
+    }
+
+    /// Select the node at a container-local position and the innermost child under the
+    /// cursor; with `add` the node joins the current selection
+    fn select_at(
+        &mut self,
+        cursor: Point<Pixels>,
+        add: bool,
+        cx: &mut Context<Self>,
+    ) -> Option<Entity<GraphNode>> {
+        let target = self.nodes[self.node_at(cursor, cx)?].clone();
+        if !add {
+            for n in &self.nodes {
+                cx.update_entity(n, |node, _| node.selected = false);
+            }
+        }
+        let (pan, zoom) = (self.pan, self.zoom);
+        let (node_id, span) = cx.update_entity(&target, |node, _| {
+            let local_x = (cursor.x - pan.x) / zoom - node.x;
+            let local_y = (cursor.y - pan.y) / zoom - node.y;
+            let child = node.child_path_at(local_x / px(1.0), local_y / px(1.0));
+            let child_span = child.as_deref().and_then(|path| node.child_span(path));
+            node.selected = true;
+            node.selected_child = child;
+            (node.id, child_span.or(node.span))
+        });
+        cx.emit(NodeSelected { node_id, span });
+        if add {
+            self.emit_selection(cx);
+        }
+        Some(target)
+    }
+
+    /// Tell the owner about a selection of several elements
+    fn emit_selection(&self, cx: &mut Context<Self>) {
+        let spans: Vec<_> = self
+            .selected_elements(cx)
+            .into_iter()
+            .map(|(_, _, span)| span)
+            .collect();
+        if spans.len() > 1 {
+            cx.emit(SelectionChanged { spans });
+        }
+    }
+
+    /// Select every node touched by the selection rectangle, in addition to the ones
+    /// selected before
+    fn finish_selection_box(&mut self, cx: &mut Context<Self>) {
+        let Some((start, end)) = self.selection_box.take() else {
+            return;
+        };
+        let (pan, zoom) = (self.pan, self.zoom);
+        let left = (start.x.min(end.x) - pan.x) / zoom;
+        let right = (start.x.max(end.x) - pan.x) / zoom;
+        let top = (start.y.min(end.y) - pan.y) / zoom;
+        let bottom = (start.y.max(end.y) - pan.y) / zoom;
+        for n in &self.nodes {
+            cx.update_entity(n, |node, _| {
+                let touched = node.x <= right
+                    && node.x + px(node.width) >= left
+                    && node.y <= bottom
+                    && node.y + px(node.height) >= top;
+                if touched && !node.selected {
+                    node.selected = true;
+                    node.selected_child = None;
+                }
+            });
+        }
+        let selected: Vec<_> = self.nodes.iter().filter(|n| n.read(cx).selected).collect();
+        if let [node] = selected.as_slice() {
+            let node = node.read(cx);
+            let (node_id, span) = (node.id, node.span);
+            cx.emit(NodeSelected { node_id, span });
+        } else {
+            self.emit_selection(cx);
+        }
+        cx.notify();
+    }
+
+    /// The selected element of every selected node with a source: the selected child, or
+    /// the node itself (with an empty path)
+    fn selected_elements(&self, cx: &App) -> Vec<(Entity<GraphNode>, ChildPath, (usize, usize))> {
+        self.nodes
+            .iter()
+            .filter_map(|n| {
+                let node = n.read(cx);
+                if !node.selected {
+                    return None;
+                }
+                let path = node.selected_child.clone().unwrap_or_default();
+                let span = if path.is_empty() {
+                    node.span?
+                } else {
+                    node.child_span(&path)?
+                };
+                Some((n.clone(), path, span))
+            })
+            .collect()
+    }
+
+    /// Ask for a new top-level element at the last right click, or in the middle of the view
+    fn add_element(&mut self, node_type: &str, cx: &mut Context<Self>) {
+        let position = self.menu_position.take().unwrap_or_else(|| {
+            point(
+                (self.container_size.width / 2.0 - self.pan.x) / self.zoom,
+                (self.container_size.height / 2.0 - self.pan.y) / self.zoom,
+            )
+        });
+        cx.emit(ModelEdit::Add {
+            node_type: node_type.to_string(),
+            position: (position.x / px(1.0), position.y / px(1.0)),
+        });
+    }
+
+    fn on_add_ecu(&mut self, _: &AddEcu, _window: &mut Window, cx: &mut Context<Self>) {
+        self.add_element("ecu", cx);
+    }
+
+    fn on_add_bus(&mut self, _: &AddBus, _window: &mut Window, cx: &mut Context<Self>) {
+        self.add_element("bus", cx);
+    }
+
+    /// Rename the selected element in an editor placed over its name
+    fn on_rename_node(&mut self, _: &RenameNode, window: &mut Window, cx: &mut Context<Self>) {
+        let Some((node, path, span)) = self.selected_elements(cx).into_iter().next() else {
+            return;
+        };
+        let (name, position, width) = {
+            let node = node.read(cx);
+            match (node.child(&path), node.child_bounds(&path)) {
+                (Some(child), Some((x, y, width, _))) => (
+                    child.name.clone(),
+                    point(node.x + px(x), node.y + px(y)),
+                    width,
+                ),
+                _ => (node.name.clone(), point(node.x, node.y), node.width),
+            }
+        };
+
+        let input = cx.new(|cx| InputState::new(window, cx).default_value(name.clone()));
+        let subscription = cx.subscribe_in(
+            &input,
+            window,
+            |this, _input, event: &InputEvent, window, cx| match event {
+                InputEvent::PressEnter { .. } | InputEvent::Blur => {
+                    this.finish_rename(true, window, cx)
+                }
+                _ => {}
+            },
+        );
+        window.focus(&input.focus_handle(cx));
+        self.renaming = Some(Renaming {
+            span,
+            nested: !path.is_empty(),
+            name,
+            input,
+            position,
+            width,
+            _subscription: subscription,
+        });
+        cx.notify();
+    }
+
+    /// Close the rename editor, asking for the new name unless cancelled or unchanged
+    fn finish_rename(&mut self, commit: bool, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(renaming) = self.renaming.take() else {
+            return;
+        };
+        let name = renaming.input.read(cx).value().trim().to_string();
+        if commit && !name.is_empty() && name != renaming.name {
+            cx.emit(ModelEdit::Rename {
+                span: renaming.span,
+                nested: renaming.nested,
+                name,
+            });
+        }
+        window.focus(&self.focus_handle);
+        cx.notify();
+    }
+
+    /// Remove the selected elements from the model
+    fn on_delete_node(&mut self, _: &DeleteNode, _window: &mut Window, cx: &mut Context<Self>) {
+        let spans: Vec<_> = self
+            .selected_elements(cx)
+            .into_iter()
+            .map(|(_, _, span)| span)
+            .collect();
+        if !spans.is_empty() {
+            cx.emit(ModelEdit::Delete { spans });
         }
     }
 
@@ -251,6 +1366,8 @@ impl Graph {
     /// Handle when node dragging ends - recalculate ArciVis routes
     pub fn handle_drag_ended(&mut self, cx: &mut Context<Self>) {
         self.is_dragging_nodes = false;
+        self.finish_change(cx);
+        cx.emit(LayoutChanged);
         if self.layout_mode == LayoutMode::ArciVis {
             // Defer the expensive recalculation to avoid entity conflicts
             let graph_entity = cx.entity();
@@ -299,7 +1416,7 @@ impl Graph {
 
         let zoom_x = available_width / content_width;
         let zoom_y = available_height / content_height;
-        let new_zoom = zoom_x.min(zoom_y).clamp(0.1, 2.0);
+        let new_zoom = zoom_x.min(zoom_y).clamp(MIN_ZOOM, MAX_ZOOM);
 
         // Center the content
         let center_x = (min_x + max_x) / 2.0;
@@ -324,335 +1441,314 @@ impl Graph {
         cx.notify();
     }
 
-    /// Apply dagre hierarchical layout to nodes
-    pub fn apply_dagre_layout(&mut self, cx: &mut Context<Self>) {
-        use dagre_rs::{DagreLayout, LayoutOptions, RankDir};
-        use petgraph::Graph as PetGraph;
-
+    /// Indices of edges the layout engines can route: both endpoints exist and differ.
+    /// Self-loops (e.g., signals between components of one node) are drawn around their node.
+    fn routable_edges(&self) -> Vec<usize> {
         let n = self.nodes.len();
-        if n == 0 {
+        self.edges
+            .iter()
+            .enumerate()
+            .filter(|(_, edge)| edge.source < n && edge.target < n && edge.source != edge.target)
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Snapshot of node sizes, positions and edges for a layout run
+    fn layout_input(&self, cx: &App) -> LayoutInput {
+        let nodes = self
+            .nodes
+            .iter()
+            .map(|n| {
+                let node = n.read(cx);
+                LayoutNode {
+                    name: node.name.clone(),
+                    width: node.width,
+                    height: node.height,
+                    x: node.x / px(1.0),
+                    y: node.y / px(1.0),
+                    bus: node.node_type == "bus",
+                }
+            })
+            .collect();
+        let edges = self
+            .routable_edges()
+            .into_iter()
+            .map(|i| (i, self.edges[i].source, self.edges[i].target))
+            .collect();
+        LayoutInput { nodes, edges }
+    }
+
+    /// Start a layout run on the background executor. It replaces a run in progress, whose
+    /// result is dropped.
+    pub fn run_layout(&mut self, run: LayoutRun, cx: &mut Context<Self>) {
+        let input = self.layout_input(cx);
+        if input.nodes.is_empty() {
             return;
         }
+        let output = cx
+            .background_executor()
+            .spawn(async move { run.run(&input) });
+        self.layout_run = Some(cx.spawn(async move |this, cx| {
+            let output = output.await;
+            this.update(cx, |graph, cx| graph.finish_layout(output, cx))
+                .ok();
+        }));
+        cx.notify();
+    }
 
-        // Get node dimensions for spacing calculation
-        let mut max_width = 0.0f32;
-        let mut max_height = 0.0f32;
-        for node_entity in &self.nodes {
-            let (w, h) = cx.read_entity(node_entity, |node, _| (node.width, node.height));
-            max_width = max_width.max(w);
-            max_height = max_height.max(h);
+    /// Drop the result of a layout run in progress, when the graph changed since it started
+    pub fn cancel_layout(&mut self, cx: &mut Context<Self>) {
+        if self.layout_run.take().is_some() {
+            cx.notify();
         }
+    }
 
-        // Build petgraph from our graph structure
-        let mut pg: PetGraph<usize, ()> = PetGraph::new();
-        let mut node_indices = Vec::with_capacity(n);
-
-        // Add nodes
-        for i in 0..n {
-            node_indices.push(pg.add_node(i));
-        }
+    /// Whether a layout run is in progress
+    pub fn is_laying_out(&self) -> bool {
+        self.layout_run.is_some()
+    }
 
-        // Add edges
-        for edge in &self.edges {
-            if edge.source < n && edge.target < n {
-                pg.add_edge(node_indices[edge.source], node_indices[edge.target], ());
+    /// Apply the result of a layout run, all nodes and routes at once
+    fn finish_layout(&mut self, output: LayoutOutput, cx: &mut Context<Self>) {
+        self.layout_run = None;
+        let Some(positions) = output.positions else {
+            for (i, path) in output.paths {
+                if let Some(edge) = self.edges.get_mut(i) {
+                    edge.path = path;
+                }
             }
+            cx.notify();
+            return;
+        };
+        // Nodes were added or removed since the run started
+        if positions.len() != self.nodes.len() {
+            cx.notify();
+            return;
         }
-
-        // Configure dagre layout with spacing based on actual node sizes
-        let options = LayoutOptions {
-            rank_dir: RankDir::TopToBottom, // Top to Bottom
-            node_sep: max_width + 30.0,     // Horizontal separation = max node width + gap
-            rank_sep: max_height + 50.0,    // Vertical separation = max node height + gap
-            ..Default::default()
+        self.apply_bus_bars(&output.bars, cx);
+
+        // A transition still in progress continues from where the nodes are now
+        self.transition = None;
+        let (zoom, pan) = (self.zoom, self.pan);
+        let mut moves = Vec::new();
+        for (node_entity, &(x, y)) in self.nodes.iter().zip(&positions) {
+            cx.update_entity(node_entity, |node, _| {
+                node.zoom = zoom;
+                node.pan = pan;
+                let from = point(node.x, node.y);
+                let to = point(px(x), px(y));
+                if !node.pinned && from != to {
+                    moves.push((node_entity.clone(), from, to));
+                }
+            });
+        }
+        let transition = Transition {
+            start: Instant::now(),
+            duration: self.layout_animation.unwrap_or_default(),
+            moves,
+            paths: output.paths,
         };
-
-        let layout = DagreLayout::with_options(options);
-        let result = layout.compute(&pg);
-
-        // Check if we got valid positions
-        if result.node_positions.is_empty() {
-            // Dagre returned no positions - fall back to simple grid layout
-            let cols = (n as f32).sqrt().ceil() as usize;
-            let spacing_x = max_width + 50.0;
-            let spacing_y = max_height + 50.0;
-
-            let zoom = self.zoom;
-            let pan = self.pan;
-            for (i, node_entity) in self.nodes.iter().enumerate() {
-                let col = i % cols;
-                let row = i / cols;
-                let x = 50.0 + col as f32 * spacing_x;
-                let y = 50.0 + row as f32 * spacing_y;
-                cx.update_entity(node_entity, move |node, _| {
-                    node.x = px(x);
-                    node.y = px(y);
-                    node.zoom = zoom;
-                    node.pan = pan;
-                });
+        if self.layout_animation.is_some() && !transition.moves.is_empty() {
+            // Routes belong to the new positions, until then the edges follow the nodes
+            for edge in &mut self.edges {
+                edge.path.clear();
             }
+            self.transition = Some(transition);
         } else {
-            // Apply positions from dagre result, scaling up if needed
-            // Dagre positions might be very small, so we need to scale them
-            let mut min_x = f32::MAX;
-            let mut min_y = f32::MAX;
-            let mut positions: Vec<(f32, f32)> = Vec::with_capacity(n);
-
-            for (i, _node_entity) in self.nodes.iter().enumerate() {
-                if let Some(&(x, y)) = result.node_positions.get(&node_indices[i]) {
-                    min_x = min_x.min(x);
-                    min_y = min_y.min(y);
-                    positions.push((x, y));
-                } else {
-                    positions.push((0.0, 0.0));
-                }
-            }
+            self.end_transition(transition, cx);
+        }
+        cx.notify();
+    }
 
-            // Offset positions so minimum is at (50, 50)
-            let offset_x = 50.0 - min_x;
-            let offset_y = 50.0 - min_y;
-
-            let zoom = self.zoom;
-            let pan = self.pan;
-            for (i, node_entity) in self.nodes.iter().enumerate() {
-                let (x, y) = positions[i];
-                let final_x = x + offset_x;
-                let final_y = y + offset_y;
-                cx.update_entity(node_entity, move |node, _| {
-                    node.x = px(final_x);
-                    node.y = px(final_y);
-                    node.zoom = zoom;
-                    node.pan = pan;
-                });
-            }
+    /// Move the nodes a step further towards their new positions; returns whether the
+    /// transition goes on
+    fn step_transition(&mut self, cx: &mut Context<Self>) -> bool {
+        let Some(transition) = &self.transition else {
+            return false;
+        };
+        let duration = transition.duration.as_secs_f32();
+        let progress = if duration > 0.0 {
+            transition.start.elapsed().as_secs_f32() / duration
+        } else {
+            1.0
+        };
+        if progress >= 1.0 {
+            self.finish_transition(cx);
+            return false;
+        }
+        let eased = ease_in_out(progress);
+        for (node, from, to) in &transition.moves {
+            cx.update_entity(node, |node, cx| {
+                node.x = from.x + (to.x - from.x) * eased;
+                node.y = from.y + (to.y - from.y) * eased;
+                cx.notify();
+            });
         }
-
         cx.notify();
+        true
     }
 
-    /// Recalculate edge paths using ArciVis orthogonal routing with current node positions
-    pub fn recalculate_arcivis_edges(&mut self, cx: &mut Context<Self>) {
-        use arcivis_layout::{ArciVisLayout, Node, Port, PortType, Position, Size};
-
-        let n = self.nodes.len();
-        if n == 0 {
-            return;
+    /// Jump to the end of a transition in progress
+    fn finish_transition(&mut self, cx: &mut Context<Self>) {
+        if let Some(transition) = self.transition.take() {
+            self.end_transition(transition, cx);
         }
+    }
 
-        // Convert GraphNode entities to arcivis-layout Node structs using CURRENT positions
-        let mut layout_nodes: Vec<Node> = Vec::with_capacity(n);
-        for node_entity in self.nodes.iter() {
-            let (name, width, height, x, y) = cx.read_entity(node_entity, |node, _| {
-                (
-                    node.name.clone(),
-                    node.width,
-                    node.height,
-                    (node.x / px(1.0)) as f32,
-                    (node.y / px(1.0)) as f32,
-                )
+    /// Put the nodes at the end of a transition and route the edges for their new positions
+    fn end_transition(&mut self, transition: Transition, cx: &mut Context<Self>) {
+        for (node, _, to) in &transition.moves {
+            cx.update_entity(node, |node, cx| {
+                node.x = to.x;
+                node.y = to.y;
+                cx.notify();
             });
-
-            // Define ports: left (input) and right (output)
-            let header_height = 28.0f64;
-            let port_size = 10.0f64;
-            let port_y = (header_height - port_size) / 2.0; // Center port vertically in header
-            let ports = vec![
-                Port {
-                    position: Position {
-                        x: -port_size / 2.0,
-                        y: port_y,
-                    }, // Left port centered on edge
-                    size: Size {
-                        width: port_size,
-                        height: port_size,
-                    },
-                    port_type: PortType::Input,
-                    id: None,
-                },
-                Port {
-                    position: Position {
-                        x: width as f64 - port_size / 2.0,
-                        y: port_y,
-                    }, // Right port centered on edge
-                    size: Size {
-                        width: port_size,
-                        height: port_size,
-                    },
-                    port_type: PortType::Output,
-                    id: None,
-                },
-            ];
-
-            // Use CURRENT node position, don't let layout reposition
-            let node = Node {
-                id: name.clone(),
-                size: Size {
-                    width: width as f64,
-                    height: height as f64,
-                },
-                position: Position {
-                    x: x as f64,
-                    y: y as f64,
-                }, // Keep current position
-                ports,
-                attributes: vec![],
-            };
-            layout_nodes.push(node);
         }
-
-        // Convert GraphEdge to edge tuples
-        let layout_edges: Vec<(usize, usize, Option<usize>, Option<usize>)> = self
-            .edges
-            .iter()
-            .filter(|edge| edge.source < n && edge.target < n)
-            .map(|edge| (edge.source, edge.target, Some(0), Some(0)))
-            .collect();
-
-        // Run the arcivis layout algorithm with fixed positions
-        let layout = ArciVisLayout::default();
-        let routed_edges = layout.route_edges_only(&layout_nodes, &layout_edges);
-
-        // Only update edge paths, don't move nodes
-        for (i, edge) in self.edges.iter_mut().enumerate() {
-            if i < routed_edges.len() {
-                let layout_edge = &routed_edges[i];
-                edge.path = layout_edge
-                    .path
-                    .iter()
-                    .map(|p| (p.x as f32, p.y as f32))
-                    .collect();
+        for (i, path) in transition.paths {
+            if let Some(edge) = self.edges.get_mut(i) {
+                edge.path = path;
             }
         }
-
+        cx.emit(LayoutChanged);
         cx.notify();
     }
 
-    pub fn apply_arcivis_layout(&mut self, cx: &mut Context<Self>) {
-        use arcivis_layout::{ArciVisLayout, Node, Port, PortType, Position, Size};
+    /// Apply dagre hierarchical layout to nodes
+    pub fn apply_dagre_layout(&mut self, cx: &mut Context<Self>) {
+        self.run_layout(LayoutRun::Dagre(self.dagre), cx);
+    }
 
-        let n = self.nodes.len();
-        if n == 0 {
-            return;
-        }
+    /// Recalculate edge paths using ArciVis orthogonal routing with current node positions
+    pub fn recalculate_arcivis_edges(&mut self, cx: &mut Context<Self>) {
+        self.run_layout(LayoutRun::ArciVisRoutes, cx);
+    }
 
-        // Convert GraphNode entities to arcivis-layout Node structs
-        let mut layout_nodes: Vec<Node> = Vec::with_capacity(n);
-        for (i, node_entity) in self.nodes.iter().enumerate() {
-            let (name, width, height) = cx.read_entity(node_entity, |node, _| {
-                (node.name.clone(), node.width, node.height)
-            });
+    pub fn apply_arcivis_layout(&mut self, cx: &mut Context<Self>) {
+        self.run_layout(LayoutRun::ArciVis, cx);
+    }
 
-            println!(
-                "Node {}: name={}, width={}, height={}",
-                i, name, width, height
-            );
-
-            // Define ports: left (input) and right (output)
-            let header_height = 28.0f64;
-            let port_size = 10.0f64;
-            let port_y = (header_height - port_size) / 2.0; // Center port vertically in header
-            let ports = vec![
-                Port {
-                    position: Position {
-                        x: -port_size / 2.0,
-                        y: port_y,
-                    }, // Left port centered on edge
-                    size: Size {
-                        width: port_size,
-                        height: port_size,
-                    },
-                    port_type: PortType::Input,
-                    id: None,
-                },
-                Port {
-                    position: Position {
-                        x: width as f64 - port_size / 2.0,
-                        y: port_y,
-                    }, // Right port centered on edge
-                    size: Size {
-                        width: port_size,
-                        height: port_size,
-                    },
-                    port_type: PortType::Output,
-                    id: None,
-                },
-            ];
-
-            // Create basic node with default position (layout will reposition)
-            let node = Node {
-                id: name.clone(),
-                size: Size {
-                    width: width as f64,
-                    height: height as f64,
-                },
-                position: Position { x: 0.0, y: 0.0 }, // Layout will set proper positions
-                ports,
-                attributes: vec![],
-            };
-            layout_nodes.push(node);
-        }
+    /// Lay out the buses as bars with the ECUs tapping them in a row below
+    pub fn apply_bus_layout(&mut self, cx: &mut Context<Self>) {
+        self.run_layout(LayoutRun::Bus, cx);
+    }
 
-        // Convert GraphEdge to edge tuples
-        let layout_edges: Vec<(usize, usize, Option<usize>, Option<usize>)> = self
-            .edges
+    /// Buses with the nodes tapping them, whichever way their edges point
+    fn bus_taps(&self, cx: &App) -> Vec<(usize, Vec<usize>)> {
+        let n = self.nodes.len();
+        let is_bus: Vec<bool> = self
+            .nodes
             .iter()
-            .filter(|edge| edge.source < n && edge.target < n)
-            .map(|edge| (edge.source, edge.target, Some(0), Some(0)))
+            .map(|node| node.read(cx).node_type == "bus")
             .collect();
+        let mut buses: Vec<(usize, Vec<usize>)> = (0..n)
+            .filter(|&i| is_bus[i])
+            .map(|i| (i, Vec::new()))
+            .collect();
+        for edge in self.edges.iter().filter(|e| e.source < n && e.target < n) {
+            let (bus, tap) = match (is_bus[edge.source], is_bus[edge.target]) {
+                (true, false) => (edge.source, edge.target),
+                (false, true) => (edge.target, edge.source),
+                _ => continue,
+            };
+            if let Some((_, taps)) = buses.iter_mut().find(|(b, _)| *b == bus)
+                && !taps.contains(&tap)
+            {
+                taps.push(tap);
+            }
+        }
+        buses
+    }
 
-        // Run the arcivis layout algorithm
-        let layout = ArciVisLayout::default();
-        let result = layout.layout(layout_nodes, layout_edges);
-
-        //print!("{:?}", &result);
-        // Generate SVG for debugging
-        use arcivis_layout::generate_svg;
-        generate_svg(&result, "arcivis_layout.svg", true, true);
-        println!("Generated SVG: arcivis_layout.svg");
+    /// Middles of the nodes tapping a bus
+    fn tap_centers(&self, taps: &[usize], cx: &App) -> Vec<f32> {
+        taps.iter()
+            .map(|&tap| {
+                let tap = self.nodes[tap].read(cx);
+                tap.x / px(1.0) + tap.width / 2.0
+            })
+            .collect()
+    }
 
-        println!("Layout completed, result has {} nodes", result.nodes.len());
-        for (i, node) in result.nodes.iter().enumerate() {
-            println!(
-                "Result node {}: id={}, x={}, y={}, w={}, h={}",
-                i, node.id, node.position.x, node.position.y, node.size.width, node.size.height
-            );
+    /// Draw the buses as bars of the widths a bus layout run computed, and every other node
+    /// as a box
+    fn apply_bus_bars(&mut self, bars: &[(usize, f32)], cx: &mut Context<Self>) {
+        for (i, node) in self.nodes.iter().enumerate() {
+            let width = bars.iter().find(|(bus, _)| *bus == i).map(|&(_, w)| w);
+            set_bar(node, None, width, cx);
         }
+    }
 
-        // Apply the resulting positions back to GraphNode entities
-        let zoom = self.zoom;
-        let pan = self.pan;
-        for (i, node_entity) in self.nodes.iter().enumerate() {
-            if i < result.nodes.len() {
-                let node = &result.nodes[i];
-                println!(
-                    "Layout result node {}: x={}, y={}",
-                    i, node.position.x, node.position.y
-                );
-                cx.update_entity(node_entity, |graph_node, _| {
-                    graph_node.x = px(node.position.x as f32);
-                    graph_node.y = px(node.position.y as f32);
-                    graph_node.zoom = zoom;
-                    graph_node.pan = pan;
-                });
-            }
+    /// Reach the bars over the nodes tapping them when the model or the arrangement changed.
+    /// A bar keeps its left end unless a tap now lies further left.
+    fn fit_bus_bars(&mut self, cx: &mut Context<Self>) {
+        for (bus, taps) in self.bus_taps(cx) {
+            let x = self.nodes[bus].read(cx).x / px(1.0);
+            let (x, width) = match bar_span(self.tap_centers(&taps, cx).into_iter()) {
+                Some((left, width)) => (x.min(left), left + width - x.min(left)),
+                // A bus nothing taps is a bar of the size of its box
+                None => (x, 0.0),
+            };
+            set_bar(&self.nodes[bus], Some(x), Some(width), cx);
         }
+    }
 
-        // Update edge paths from layout result
-        for (i, edge) in self.edges.iter_mut().enumerate() {
-            if i < result.edges.len() {
-                let layout_edge = &result.edges[i];
-                edge.path = layout_edge
-                    .path
-                    .iter()
-                    .map(|p| (p.x as f32, p.y as f32))
-                    .collect();
+    /// Stretch the bars of the buses tapped by `moved` nodes over their taps again
+    fn stretch_bus_bars(&mut self, moved: &[usize], cx: &mut Context<Self>) {
+        for (bus, taps) in self.bus_taps(cx) {
+            if !taps.iter().any(|tap| moved.contains(tap)) {
+                continue;
+            }
+            if let Some((x, width)) = bar_span(self.tap_centers(&taps, cx).into_iter()) {
+                set_bar(&self.nodes[bus], Some(x), Some(width), cx);
             }
         }
+    }
 
-        cx.notify();
+    /// Whether each node moves with a drag of `dragged`, which is being updated and is told
+    /// apart by its entity id
+    fn moved_nodes(&self, dragged: EntityId, selected: bool, cx: &App) -> Vec<bool> {
+        self.nodes
+            .iter()
+            .map(|n| {
+                n.entity_id() == dragged || (selected && cx.read_entity(n, |node, _| node.selected))
+            })
+            .collect()
     }
 }
 
+/// Identity of an edge across model updates: endpoint keys and children, and its kind
+type RouteKey<'a> = (
+    &'a str,
+    Option<ChildPath>,
+    &'a str,
+    Option<ChildPath>,
+    EdgeKind,
+);
+
+fn route_key<'a>(keys: &'a [String], edge: &GraphEdge) -> Option<RouteKey<'a>> {
+    Some((
+        keys.get(edge.source)?.as_str(),
+        edge.source_child.clone(),
+        keys.get(edge.target)?.as_str(),
+        edge.target_child.clone(),
+        edge.kind,
+    ))
+}
+
+/// Draw a bus as a bar of `width`, moving its left end to `x` if given, or as a box again
+/// with no width
+fn set_bar(node: &Entity<GraphNode>, x: Option<f32>, width: Option<f32>, cx: &mut App) {
+    cx.update_entity(node, |node, cx| {
+        let x = x.map(px).unwrap_or(node.x);
+        if (node.x, node.bar_width) != (x, width) {
+            node.x = x;
+            node.bar_width = width;
+            (node.width, node.height) = node.estimate_dimensions();
+            cx.notify();
+        }
+    });
+}
+
 fn parameter_button<F>(
     label: &str,
     text_color: Hsla,
@@ -679,8 +1775,140 @@ where
         )
 }
 
+/// Button of the Dagre settings, highlighted when `active`; pressing it changes the settings
+/// with `update`, undoably
+fn dagre_option<F>(
+    label: &str,
+    active: bool,
+    colors: (Hsla, Hsla, Hsla),
+    cx: &mut Context<Graph>,
+    update: F,
+) -> Div
+where
+    F: Fn(&mut DagreSettings) + 'static,
+{
+    let (text_color, border_color, active_color) = colors;
+    parameter_button(label, text_color, border_color, cx, move |this, cx| {
+        let mut settings = this.dagre;
+        update(&mut settings);
+        this.record_layout(cx);
+        this.set_dagre_settings(settings, cx);
+    })
+    .when(active, |this| this.bg(active_color))
+}
+
+/// Settings row of a Dagre separation, with buttons to narrow and widen it
+fn separation_row(
+    label: &str,
+    value: f32,
+    colors: (Hsla, Hsla, Hsla),
+    cx: &mut Context<Graph>,
+    field: fn(&mut DagreSettings) -> &mut f32,
+) -> Div {
+    div()
+        .flex()
+        .items_center()
+        .gap_2()
+        .child(div().w(px(110.0)).child(label.to_string()))
+        .child(dagre_option("-", false, colors, cx, move |settings| {
+            let value = field(settings);
+            *value = (*value - 10.0).max(0.0);
+        }))
+        .child(div().w(px(32.0)).child(format!("{}", value)))
+        .child(dagre_option("+", false, colors, cx, move |settings| {
+            *field(settings) += 10.0;
+        }))
+}
+
+/// Vertical drop between a bus bar and a node tapping it, at the middle of the node and on
+/// to the anchor of a nested child. Runs from the bar down when `from_bar`, else back up.
+fn bus_drop(
+    bar: &GraphNode,
+    tap: &GraphNode,
+    child: Option<&[usize]>,
+    from_bar: bool,
+) -> Vec<Point<Pixels>> {
+    let x = tap.x + px(tap.width / 2.0);
+    let (bar_y, tap_y) = if bar.y < tap.y {
+        (bar.y + px(bar.height), tap.y)
+    } else {
+        (bar.y, tap.y + px(tap.height))
+    };
+    let mut points = vec![point(x, bar_y), point(x, tap_y)];
+    if child.is_some() {
+        points.push(tap.edge_anchor(child, !from_bar));
+    }
+    if !from_bar {
+        points.reverse();
+    }
+    points
+}
+
+/// Line through the bends of a routed edge: straight segments, horizontal and vertical ones
+/// turning halfway between the ranks, or a Catmull-Rom spline
+fn route_through(
+    points: &[Point<Pixels>],
+    routing: EdgeRouting,
+    horizontal_ranks: bool,
+) -> Vec<Point<Pixels>> {
+    match routing {
+        EdgeRouting::Straight => points.to_vec(),
+        EdgeRouting::Manhattan => {
+            let mut route = vec![points[0]];
+            for pair in points.windows(2) {
+                let (a, b) = (pair[0], pair[1]);
+                if a.x != b.x && a.y != b.y {
+                    if horizontal_ranks {
+                        let middle = (a.x + b.x) / 2.0;
+                        route.extend([point(middle, a.y), point(middle, b.y)]);
+                    } else {
+                        let middle = (a.y + b.y) / 2.0;
+                        route.extend([point(a.x, middle), point(b.x, middle)]);
+                    }
+                }
+                route.push(b);
+            }
+            route
+        }
+        EdgeRouting::Spline => {
+            const STEPS: usize = 8;
+            let last = points.len() - 1;
+            let mut route = vec![points[0]];
+            for k in 0..last {
+                let p0 = points[k.saturating_sub(1)];
+                let (p1, p2) = (points[k], points[k + 1]);
+                let p3 = points[(k + 2).min(last)];
+                for step in 1..=STEPS {
+                    let t = step as f32 / STEPS as f32;
+                    let (t2, t3) = (t * t, t * t * t);
+                    let along = |v0: f32, v1: f32, v2: f32, v3: f32| {
+                        0.5 * (2.0 * v1
+                            + (v2 - v0) * t
+                            + (2.0 * v0 - 5.0 * v1 + 4.0 * v2 - v3) * t2
+                            + (3.0 * v1 - v0 - 3.0 * v2 + v3) * t3)
+                    };
+                    let x = along(
+                        p0.x / px(1.0),
+                        p1.x / px(1.0),
+                        p2.x / px(1.0),
+                        p3.x / px(1.0),
+                    );
+                    let y = along(
+                        p0.y / px(1.0),
+                        p1.y / px(1.0),
+                        p2.y / px(1.0),
+                        p3.y / px(1.0),
+                    );
+                    route.push(point(px(x), px(y)));
+                }
+            }
+            route
+        }
+    }
+}
+
 impl Render for Graph {
-    fn render(&mut self, _window: &mut Window, graph_cx: &mut Context<Self>) -> impl IntoElement {
+    fn render(&mut self, window: &mut Window, graph_cx: &mut Context<Self>) -> impl IntoElement {
         // Capture container bounds and update nodes with offset, trigger layout if needed
         let nodes_for_offset = self.nodes.clone();
         let graph_entity_for_offset = graph_cx.entity();
@@ -722,6 +1950,8 @@ impl Render for Graph {
         let nodes = self.nodes.clone();
         let edges = self.edges.clone();
         let layout_mode = self.layout_mode;
+        let edge_routing = self.edge_routing;
+        let horizontal_ranks = self.dagre.rank_direction.is_horizontal();
         let edges_canvas = canvas(
             |_bounds, _window, _cx| (),
             move |bounds, _state, window, cx| {
@@ -729,15 +1959,13 @@ impl Render for Graph {
                 let offset = bounds.origin;
                 let thickness = (1.0f32 * zoom).max(1.0);
 
-                // Port positioning constants (must match node.rs)
-                let header_height = 28.0f32;
-                // Port vertical center is at header_height / 2 from node top
-                let port_y_offset = header_height / 2.0;
-
                 // Port colors for highlighted edges
                 let source_color = rgb(0xff8844); // Orange (outgoing port)
                 let target_color = rgb(0x4488ff); // Blue (incoming port)
                 let normal_color = rgb(0x323232);
+                let signal_color = rgb(0x3a9a5a); // Green (signal data flow)
+                let service_color = rgb(0x8a6acc); // Purple (service connection)
+                let reference_color = rgb(0x5a7a9a); // Grey-blue (interface reference)
 
                 // Helper closure to draw a thick line segment to a path
                 let draw_segment = |path: &mut gpui::Path<Pixels>,
@@ -761,6 +1989,38 @@ impl Render for Graph {
                     path.push_triangle((p2a, p1b, p2b), st);
                 };
 
+                // Helper closure to draw a dashed line segment, dashes restart at each segment
+                let dash_len = 6.0f32 * zoom;
+                let gap_len = 4.0f32 * zoom;
+                let draw_dashed = |path: &mut gpui::Path<Pixels>,
+                                   p1: Point<Pixels>,
+                                   p2: Point<Pixels>,
+                                   half_thickness: f32| {
+                    let dir = point(p2.x - p1.x, p2.y - p1.y);
+                    let len = dir.magnitude() as f32;
+                    let mut t = 0.0f32;
+                    while t < len {
+                        let end = (t + dash_len).min(len);
+                        let a = point(p1.x + dir.x * (t / len), p1.y + dir.y * (t / len));
+                        let b = point(p1.x + dir.x * (end / len), p1.y + dir.y * (end / len));
+                        draw_segment(path, a, b, half_thickness);
+                        t += dash_len + gap_len;
+                    }
+                };
+
+                // Service and reference edges stay dashed, all others are solid
+                let stroke = |path: &mut gpui::Path<Pixels>,
+                              kind: EdgeKind,
+                              p1: Point<Pixels>,
+                              p2: Point<Pixels>,
+                              half_thickness: f32| {
+                    if kind.is_dashed() {
+                        draw_dashed(path, p1, p2, half_thickness);
+                    } else {
+                        draw_segment(path, p1, p2, half_thickness);
+                    }
+                };
+
                 // Collect edge data with selection state
                 #[derive(Clone, Copy)]
                 enum EdgeSelection {
@@ -773,20 +2033,59 @@ impl Render for Graph {
                 struct EdgeData {
                     path: Vec<Point<Pixels>>, // Edge path points
                     selection: EdgeSelection,
+                    kind: EdgeKind,
+                    count: usize, // Number of edges merged into this one
                 }
 
-                let mut edge_data: Vec<EdgeData> = Vec::with_capacity(edges.len());
-
+                // Edges into hidden children attach to their innermost visible container.
+                // Edges that end up between the same endpoints are merged into one.
+                type Endpoint = (usize, Option<ChildPath>);
+                let mut merged: Vec<(&GraphEdge, Endpoint, Endpoint, usize)> = Vec::new();
+                let mut merged_index: HashMap<(Endpoint, Endpoint, EdgeKind), usize> =
+                    HashMap::new();
                 for edge in &edges {
                     let i = edge.source;
                     let j = edge.target;
                     if i >= nodes.len() || j >= nodes.len() {
                         continue;
                     }
+                    let source = (
+                        i,
+                        cx.read_entity(&nodes[i], |n, _| {
+                            n.visible_path(edge.source_child.as_deref())
+                        }),
+                    );
+                    let target = (
+                        j,
+                        cx.read_entity(&nodes[j], |n, _| {
+                            n.visible_path(edge.target_child.as_deref())
+                        }),
+                    );
+                    // Connections within a collapsed container are not shown
+                    if edge.has_child_endpoint() && source == target {
+                        continue;
+                    }
+                    let key = (source.clone(), target.clone(), edge.kind);
+                    match merged_index.get(&key) {
+                        Some(&index) => merged[index].3 += 1,
+                        None => {
+                            merged_index.insert(key, merged.len());
+                            merged.push((edge, source, target, 1));
+                        }
+                    }
+                }
 
+                let mut edge_data: Vec<EdgeData> = Vec::with_capacity(merged.len());
+
+                for (edge, (i, source_child), (j, target_child), count) in merged {
+                    // A selected nested child narrows highlighting to the edges attached to it
                     let (source_selected, target_selected) = {
-                        let source_sel = cx.read_entity(&nodes[i], |n, _| n.selected);
-                        let target_sel = cx.read_entity(&nodes[j], |n, _| n.selected);
+                        let source_sel = cx.read_entity(&nodes[i], |n, _| {
+                            n.is_endpoint_selected(source_child.as_deref())
+                        });
+                        let target_sel = cx.read_entity(&nodes[j], |n, _| {
+                            n.is_endpoint_selected(target_child.as_deref())
+                        });
                         (source_sel, target_sel)
                     };
 
@@ -797,69 +2096,125 @@ impl Render for Graph {
                         (false, false) => EdgeSelection::None,
                     };
 
-                    // Use stored path only for ArciVis mode, otherwise calculate ports dynamically
-                    let path_points: Vec<Point<Pixels>> =
-                        if layout_mode == LayoutMode::ArciVis && !edge.path.is_empty() {
-                            // Transform stored path to screen coordinates
-                            edge.path
-                                .iter()
-                                .map(|(x, y)| {
-                                    point(
-                                        offset.x + pan.x + px(*x) * zoom,
-                                        offset.y + pan.y + px(*y) * zoom,
-                                    )
-                                })
-                                .collect()
-                        } else {
-                            // Dynamic port-based routing for Force, Dagre, or when paths are invalid
-                            let (x1, y1) = cx.read_entity(&nodes[i], |n, _| {
-                                (n.x + px(n.width), n.y + px(port_y_offset))
-                            });
-                            let (x2, y2) =
-                                cx.read_entity(&nodes[j], |n, _| (n.x, n.y + px(port_y_offset)));
-
-                            // Apply Manhattan routing: right → up → horizontal → down → left
-                            let clearance = px(30.0) * zoom; // Vertical clearance above nodes
-                            let stub_len = px(15.0) * zoom; // Horizontal stub from port
-
-                            // Stub out from source port (right)
-                            let s1 = point(x1 + stub_len, y1);
-                            // Stub in to target port (left)
-                            let s2 = point(x2 - stub_len, y2);
-
-                            // Route above - find the minimum y and go above it
-                            let min_y = y1.min(y2);
-                            let route_y = min_y - clearance;
-
-                            // 5 segments: stub right, up, horizontal, down, stub left
-                            let c1 = point(s1.x, route_y); // Up from source stub
-                            let c2 = point(s2.x, route_y); // Horizontal to above target
+                    // Bus bars are tapped by a vertical drop from the node below them
+                    let is_bar = |node: &Entity<GraphNode>| {
+                        cx.read_entity(node, |n, _| n.bar_width.is_some())
+                    };
+                    let drop = match (is_bar(&nodes[i]), is_bar(&nodes[j])) {
+                        (true, false) if source_child.is_none() => {
+                            Some((i, j, &target_child, true))
+                        }
+                        (false, true) if target_child.is_none() => {
+                            Some((j, i, &source_child, false))
+                        }
+                        _ => None,
+                    };
 
-                            vec![
-                                point(offset.x + pan.x + x1 * zoom, offset.y + pan.y + y1 * zoom), // Source port
-                                point(
-                                    offset.x + pan.x + s1.x * zoom,
-                                    offset.y + pan.y + s1.y * zoom,
-                                ), // Stub right
-                                point(
-                                    offset.x + pan.x + c1.x * zoom,
-                                    offset.y + pan.y + c1.y * zoom,
-                                ), // Up
-                                point(
-                                    offset.x + pan.x + c2.x * zoom,
-                                    offset.y + pan.y + c2.y * zoom,
-                                ), // Horizontal
+                    // Use stored path only for ArciVis mode, otherwise calculate ports dynamically.
+                    // ArciVis routes node ports, so edges attached to nested children are routed here.
+                    let path_points: Vec<Point<Pixels>> = if let Some((bar, tap, child, from_bar)) =
+                        drop
+                    {
+                        cx.read_entity(&nodes[bar], |bar, cx| {
+                            cx.read_entity(&nodes[tap], |tap, _| {
+                                bus_drop(bar, tap, child.as_deref(), from_bar)
+                            })
+                        })
+                        .into_iter()
+                        .map(|p| {
+                            point(offset.x + pan.x + p.x * zoom, offset.y + pan.y + p.y * zoom)
+                        })
+                        .collect()
+                    } else if layout_mode == LayoutMode::ArciVis
+                        && !edge.path.is_empty()
+                        && source_child.is_none()
+                        && target_child.is_none()
+                    {
+                        // Transform stored path to screen coordinates
+                        edge.path
+                            .iter()
+                            .map(|(x, y)| {
                                 point(
-                                    offset.x + pan.x + s2.x * zoom,
-                                    offset.y + pan.y + s2.y * zoom,
-                                ), // Down
-                                point(offset.x + pan.x + x2 * zoom, offset.y + pan.y + y2 * zoom), // Target port
-                            ]
-                        };
+                                    offset.x + pan.x + px(*x) * zoom,
+                                    offset.y + pan.y + px(*y) * zoom,
+                                )
+                            })
+                            .collect()
+                    } else if layout_mode == LayoutMode::Dagre && edge.path.len() >= 2 {
+                        // Dagre's bends between the live port anchors, so dragged nodes keep
+                        // their edges attached
+                        let start = cx.read_entity(&nodes[i], |n, _| {
+                            n.edge_anchor(source_child.as_deref(), true)
+                        });
+                        let end = cx.read_entity(&nodes[j], |n, _| {
+                            n.edge_anchor(target_child.as_deref(), false)
+                        });
+                        let mut points = vec![start];
+                        points.extend(
+                            edge.path[1..edge.path.len() - 1]
+                                .iter()
+                                .map(|&(x, y)| point(px(x), px(y))),
+                        );
+                        points.push(end);
+                        route_through(&points, edge_routing, horizontal_ranks)
+                            .into_iter()
+                            .map(|p| {
+                                point(offset.x + pan.x + p.x * zoom, offset.y + pan.y + p.y * zoom)
+                            })
+                            .collect()
+                    } else {
+                        // Dynamic port-based routing for Force, or when edges have no route yet
+                        let Point { x: x1, y: y1 } = cx.read_entity(&nodes[i], |n, _| {
+                            n.edge_anchor(source_child.as_deref(), true)
+                        });
+                        let Point { x: x2, y: y2 } = cx.read_entity(&nodes[j], |n, _| {
+                            n.edge_anchor(target_child.as_deref(), false)
+                        });
+
+                        // Apply Manhattan routing: right → up → horizontal → down → left
+                        let clearance = px(30.0) * zoom; // Vertical clearance above nodes
+                        let stub_len = px(15.0) * zoom; // Horizontal stub from port
+
+                        // Stub out from source port (right)
+                        let s1 = point(x1 + stub_len, y1);
+                        // Stub in to target port (left)
+                        let s2 = point(x2 - stub_len, y2);
+
+                        // Route above - find the minimum y and go above it
+                        let min_y = y1.min(y2);
+                        let route_y = min_y - clearance;
+
+                        // 5 segments: stub right, up, horizontal, down, stub left
+                        let c1 = point(s1.x, route_y); // Up from source stub
+                        let c2 = point(s2.x, route_y); // Horizontal to above target
+
+                        vec![
+                            point(offset.x + pan.x + x1 * zoom, offset.y + pan.y + y1 * zoom), // Source port
+                            point(
+                                offset.x + pan.x + s1.x * zoom,
+                                offset.y + pan.y + s1.y * zoom,
+                            ), // Stub right
+                            point(
+                                offset.x + pan.x + c1.x * zoom,
+                                offset.y + pan.y + c1.y * zoom,
+                            ), // Up
+                            point(
+                                offset.x + pan.x + c2.x * zoom,
+                                offset.y + pan.y + c2.y * zoom,
+                            ), // Horizontal
+                            point(
+                                offset.x + pan.x + s2.x * zoom,
+                                offset.y + pan.y + s2.y * zoom,
+                            ), // Down
+                            point(offset.x + pan.x + x2 * zoom, offset.y + pan.y + y2 * zoom), // Target port
+                        ]
+                    };
 
                     edge_data.push(EdgeData {
                         path: path_points,
                         selection,
+                        kind: edge.kind,
+                        count,
                     });
                 }
 
@@ -899,23 +2254,34 @@ impl Render for Graph {
                 window.paint_path(outgoing_glow_path, rgba(0xff884460)); // Orange glow
                 window.paint_path(incoming_glow_path, rgba(0x4488ff60)); // Blue glow
 
-                // Draw normal (non-selected) edges
+                // Draw normal (non-selected) edges, colored by their kind
                 let mut normal_path = gpui::Path::new(offset);
+                let mut signal_path = gpui::Path::new(offset);
+                let mut service_path = gpui::Path::new(offset);
+                let mut reference_path = gpui::Path::new(offset);
                 for edge in &edge_data {
-                    if matches!(edge.selection, EdgeSelection::None) {
-                        if edge.path.len() >= 2 {
-                            for i in 0..edge.path.len() - 1 {
-                                draw_segment(
-                                    &mut normal_path,
-                                    edge.path[i],
-                                    edge.path[i + 1],
-                                    thickness,
-                                );
+                    if matches!(edge.selection, EdgeSelection::None) && edge.path.len() >= 2 {
+                        for i in 0..edge.path.len() - 1 {
+                            let (p1, p2) = (edge.path[i], edge.path[i + 1]);
+                            match edge.kind {
+                                EdgeKind::Link => draw_segment(&mut normal_path, p1, p2, thickness),
+                                EdgeKind::Signal => {
+                                    draw_segment(&mut signal_path, p1, p2, thickness)
+                                }
+                                EdgeKind::Service => {
+                                    draw_dashed(&mut service_path, p1, p2, thickness)
+                                }
+                                EdgeKind::Reference => {
+                                    draw_dashed(&mut reference_path, p1, p2, thickness)
+                                }
                             }
                         }
                     }
                 }
                 window.paint_path(normal_path, normal_color);
+                window.paint_path(signal_path, signal_color);
+                window.paint_path(service_path, service_color);
+                window.paint_path(reference_path, reference_color);
 
                 // Draw selected edges with appropriate colors
                 // Orange for outgoing (source selected), blue for incoming (target selected)
@@ -926,8 +2292,9 @@ impl Render for Graph {
                         EdgeSelection::SourceSelected | EdgeSelection::BothSelected => {
                             if edge.path.len() >= 2 {
                                 for i in 0..edge.path.len() - 1 {
-                                    draw_segment(
+                                    stroke(
                                         &mut outgoing_path,
+                                        edge.kind,
                                         edge.path[i],
                                         edge.path[i + 1],
                                         thickness * 2.0,
@@ -938,8 +2305,9 @@ impl Render for Graph {
                         EdgeSelection::TargetSelected => {
                             if edge.path.len() >= 2 {
                                 for i in 0..edge.path.len() - 1 {
-                                    draw_segment(
+                                    stroke(
                                         &mut incoming_path,
+                                        edge.kind,
                                         edge.path[i],
                                         edge.path[i + 1],
                                         thickness * 2.0,
@@ -952,15 +2320,62 @@ impl Render for Graph {
                 }
                 window.paint_path(outgoing_path, source_color); // Orange for outgoing
                 window.paint_path(incoming_path, target_color); // Blue for incoming
+
+                // Count badges on merged edges, centered on their middle segment
+                let font_size = px(9.0 * zoom);
+                let font = window.text_style().font();
+                for edge in edge_data
+                    .iter()
+                    .filter(|e| e.count > 1 && e.path.len() >= 2)
+                {
+                    let segment = (edge.path.len() - 2) / 2;
+                    let (a, b) = (edge.path[segment], edge.path[segment + 1]);
+                    let center = point((a.x + b.x) / 2.0, (a.y + b.y) / 2.0);
+
+                    let text: SharedString = edge.count.to_string().into();
+                    let run = TextRun {
+                        len: text.len(),
+                        font: font.clone(),
+                        color: gpui::white().into(),
+                        background_color: None,
+                        underline: None,
+                        strikethrough: None,
+                    };
+                    let line = window
+                        .text_system()
+                        .shape_line(text, font_size, &[run], None);
+
+                    let badge_size = size(line.width + font_size, font_size * 1.6);
+                    let origin = point(
+                        center.x - badge_size.width / 2.0,
+                        center.y - badge_size.height / 2.0,
+                    );
+                    let color = match edge.kind {
+                        EdgeKind::Link => normal_color,
+                        EdgeKind::Signal => signal_color,
+                        EdgeKind::Service => service_color,
+                        EdgeKind::Reference => reference_color,
+                    };
+                    window.paint_quad(
+                        fill(Bounds::new(origin, badge_size), color)
+                            .corner_radii(badge_size.height / 2.0),
+                    );
+                    let text_origin = point(
+                        center.x - line.width / 2.0,
+                        center.y - badge_size.height / 2.0,
+                    );
+                    line.paint(text_origin, badge_size.height, window, cx).ok();
+                }
             },
         )
         .absolute()
         .size_full();
 
-        // Node entities render above edges
+        // Node entities render above edges, dimmed while the source document is invalid
         let graph_canvas = div()
             .relative()
             .size_full()
+            .when(self.stale.is_some(), |this| this.opacity(0.5))
             .child(bounds_tracker)
             .child(edges_canvas)
             .children(self.nodes.iter().cloned());
@@ -975,11 +2390,115 @@ impl Render for Graph {
         let layout_mode = self.layout_mode;
         let controls_panel = {
             let zoom_out = parameter_button("-", text_color, border_color, graph_cx, |this, cx| {
+                this.record_layout(cx);
                 this.set_zoom(this.zoom - 0.1, cx);
             });
             let zoom_in = parameter_button("+", text_color, border_color, graph_cx, |this, cx| {
+                this.record_layout(cx);
                 this.set_zoom(this.zoom + 0.1, cx);
             });
+            // Options of the Dagre layout, in a popover to keep the panel small
+            let colors = (text_color, border_color, bg_color);
+            let dagre = self.dagre;
+            let edge_routing = self.edge_routing;
+            let option_row = |label: &str| {
+                div()
+                    .flex()
+                    .items_center()
+                    .gap_2()
+                    .child(div().w(px(110.0)).child(label.to_string()))
+            };
+            let dagre_settings = Popover::new("dagre-settings")
+                .trigger(
+                    Button::new("dagre-settings-button")
+                        .outline()
+                        .small()
+                        .label("Dagre…"),
+                )
+                .child(
+                    div()
+                        .flex()
+                        .flex_col()
+                        .gap_2()
+                        .text_sm()
+                        // Keep clicks in the popover from reaching the canvas
+                        .on_mouse_down(gpui::MouseButton::Left, |_e, _w, cx| cx.stop_propagation())
+                        .child(option_row("Direction").children(RankDirection::ALL.map(
+                            |direction| {
+                                dagre_option(
+                                    direction.label(),
+                                    dagre.rank_direction == direction,
+                                    colors,
+                                    graph_cx,
+                                    move |settings| settings.rank_direction = direction,
+                                )
+                            },
+                        )))
+                        .child(separation_row(
+                            "Node separation",
+                            dagre.node_separation,
+                            colors,
+                            graph_cx,
+                            |settings| &mut settings.node_separation,
+                        ))
+                        .child(separation_row(
+                            "Rank separation",
+                            dagre.rank_separation,
+                            colors,
+                            graph_cx,
+                            |settings| &mut settings.rank_separation,
+                        ))
+                        .child(separation_row(
+                            "Edge separation",
+                            dagre.edge_separation,
+                            colors,
+                            graph_cx,
+                            |settings| &mut settings.edge_separation,
+                        ))
+                        .child(
+                            option_row("Alignment")
+                                .child(dagre_option(
+                                    "Balanced",
+                                    dagre.align.is_none(),
+                                    colors,
+                                    graph_cx,
+                                    |settings| settings.align = None,
+                                ))
+                                .children(DagreAlign::ALL.map(|align| {
+                                    dagre_option(
+                                        align.label(),
+                                        dagre.align == Some(align),
+                                        colors,
+                                        graph_cx,
+                                        move |settings| settings.align = Some(align),
+                                    )
+                                })),
+                        )
+                        .child(
+                            option_row("Ranker").children(DagreRanker::ALL.map(|ranker| {
+                                dagre_option(
+                                    ranker.label(),
+                                    dagre.ranker == ranker,
+                                    colors,
+                                    graph_cx,
+                                    move |settings| settings.ranker = ranker,
+                                )
+                            })),
+                        )
+                        .child(
+                            option_row("Edges").children(EdgeRouting::ALL.map(|routing| {
+                                parameter_button(
+                                    routing.label(),
+                                    text_color,
+                                    border_color,
+                                    graph_cx,
+                                    move |this, cx| this.set_edge_routing(routing, cx),
+                                )
+                                .when(edge_routing == routing, |this| this.bg(bg_color))
+                            })),
+                        ),
+                );
+
             let fit_button = div()
                 .px(px(8.0))
                 .py(px(4.0))
@@ -993,7 +2512,9 @@ impl Render for Graph {
                 .on_mouse_down(
                     gpui::MouseButton::Left,
                     graph_cx.listener(|this, _e: &gpui::MouseDownEvent, _w, cx| {
+                        this.record_layout(cx);
                         this.fit_to_content(cx);
+                        cx.emit(ViewChanged);
                     }),
                 );
 
@@ -1002,6 +2523,7 @@ impl Render for Graph {
                 LayoutMode::Force => "Force",
                 LayoutMode::Dagre => "Dagre",
                 LayoutMode::ArciVis => "ArciVis",
+                LayoutMode::Bus => "Bus",
             };
             let layout_button = div()
                 .px(px(8.0))
@@ -1016,6 +2538,7 @@ impl Render for Graph {
                 .on_mouse_down(
                     gpui::MouseButton::Left,
                     graph_cx.listener(|this, _e: &gpui::MouseDownEvent, _w, cx| {
+                        this.record_layout(cx);
                         this.layout_mode = match this.layout_mode {
                             LayoutMode::Force => {
                                 // Clear stored paths when switching to Dagre
@@ -1038,13 +2561,21 @@ impl Render for Graph {
                                 LayoutMode::ArciVis
                             }
                             LayoutMode::ArciVis => {
-                                // Clear stored paths when switching back to Force
+                                // Clear stored paths when switching to Bus
                                 for edge in &mut this.edges {
                                     edge.path.clear();
                                 }
+                                this.apply_bus_layout(cx);
+                                this.playing = false; // Stop force simulation
+                                LayoutMode::Bus
+                            }
+                            LayoutMode::Bus => {
+                                // Buses are boxes again outside the bus layout
+                                this.apply_bus_bars(&[], cx);
                                 LayoutMode::Force
                             }
                         };
+                        cx.emit(LayoutChanged);
                         cx.notify();
                     }),
                 );
@@ -1069,8 +2600,85 @@ impl Render for Graph {
                 .child(fit_button)
                 .child(div().w(px(8.0))) // spacer
                 .child(layout_button)
+                .child(
+                    div()
+                        .on_mouse_down(gpui::MouseButton::Left, |_e, _w, cx| cx.stop_propagation())
+                        .child(dagre_settings),
+                )
+                .when(self.is_laying_out(), |this| {
+                    this.child(
+                        div()
+                            .id("layout-progress")
+                            .flex()
+                            .items_center()
+                            .gap_1()
+                            .cursor_pointer()
+                            .child(
+                                div().child("Laying out…").with_animation(
+                                    "layout-progress-pulse",
+                                    Animation::new(Duration::from_secs(1))
+                                        .repeat()
+                                        .with_easing(pulsating_between(0.4, 1.0)),
+                                    |this, delta| this.opacity(delta),
+                                ),
+                            )
+                            .child("✕")
+                            .on_mouse_down(
+                                gpui::MouseButton::Left,
+                                graph_cx.listener(|this, _e: &gpui::MouseDownEvent, _w, cx| {
+                                    cx.stop_propagation();
+                                    this.cancel_layout(cx);
+                                }),
+                            ),
+                    )
+                })
+                // Keep clicks on the panel, wherever it ends, from starting a pan on the canvas
+                .on_mouse_down(gpui::MouseButton::Left, |_e, _w, cx| cx.stop_propagation())
         };
 
+        // Banner explaining why the graph is stale, with a link to the error location
+        let stale_banner = self.stale.clone().map(|stale| {
+            let jump_span = stale.span;
+            div()
+                .absolute()
+                .bottom(px(8.0))
+                .left(px(8.0))
+                .right(px(8.0))
+                .px(px(8.0))
+                .py(px(6.0))
+                .bg(graph_cx.theme().warning.opacity(0.9))
+                .text_color(graph_cx.theme().warning_foreground)
+                .border(px(1.0))
+                .border_color(border_color)
+                .rounded(px(6.0))
+                .flex()
+                .items_center()
+                .gap_2()
+                .child(div().flex_1().child(format!(
+                    "Document has errors, showing last valid model: {}",
+                    stale.message
+                )))
+                .when_some(jump_span, |this, span| {
+                    this.child(
+                        div()
+                            .cursor_pointer()
+                            .underline()
+                            .child("Go to error")
+                            .on_mouse_down(
+                                gpui::MouseButton::Left,
+                                graph_cx.listener(
+                                    move |_this, _e: &gpui::MouseDownEvent, _w, cx| {
+                                        cx.stop_propagation();
+                                        cx.emit(JumpToSpan { span });
+                                    },
+                                ),
+                            ),
+                    )
+                })
+                // Keep clicks on the banner from starting a pan on the canvas
+                .on_mouse_down(gpui::MouseButton::Left, |_e, _w, cx| cx.stop_propagation())
+        });
+
         // Simulation canvas: runs a physics step per frame when playing in Force mode
         let graph_entity = graph_cx.entity();
         let graph_handle = graph_entity.clone();
@@ -1101,13 +2709,15 @@ impl Render for Graph {
                     let mut ys: Vec<f32> = Vec::with_capacity(n);
                     let mut widths: Vec<f32> = Vec::with_capacity(n);
                     let mut heights: Vec<f32> = Vec::with_capacity(n);
+                    let mut pinned: Vec<bool> = Vec::with_capacity(n);
                     for ent in &nodes_for_sim {
-                        let (x, y, w, h) =
-                            cx.read_entity(ent, |nd, _| (nd.x, nd.y, nd.width, nd.height));
+                        let (x, y, w, h, p) = cx
+                            .read_entity(ent, |nd, _| (nd.x, nd.y, nd.width, nd.height, nd.pinned));
                         xs.push((x / px(1.0)) as f32);
                         ys.push((y / px(1.0)) as f32);
                         widths.push(w);
                         heights.push(h);
+                        pinned.push(p);
                     }
 
                     let mut fx = vec![0.0f32; n];
@@ -1268,8 +2878,8 @@ impl Render for Graph {
                         }
                     }
 
-                    // Write back
-                    for i in 0..n {
+                    // Write back, pinned nodes stay where they were placed
+                    for i in (0..n).filter(|&i| !pinned[i]) {
                         let nx = px(xs[i]);
                         let ny = px(ys[i]);
                         let ent = nodes_for_sim[i].clone();
@@ -1290,68 +2900,148 @@ impl Render for Graph {
         .absolute()
         .size_full();
 
-        div()
+        // Transition canvas: moves the nodes a step per frame while they move to a new layout
+        let graph_for_transition = graph_cx.entity();
+        let transition_canvas = self.transition.is_some().then(|| {
+            canvas(
+                move |_bounds, _window, _cx| (),
+                move |_bounds, _state, window, cx| {
+                    let running = cx.update_entity(&graph_for_transition, |graph, cx| {
+                        graph.step_transition(cx)
+                    });
+                    if running {
+                        window.request_animation_frame();
+                    }
+                },
+            )
+            .absolute()
+            .size_full()
+        });
+
+        // Editor of a rename in progress, over the name of the renamed element. It stays out of
+        // the graph's key context so typing does not trigger the graph's key bindings.
+        let rename_editor = self.renaming.as_ref().map(|renaming| {
+            div()
+                .absolute()
+                .left(self.pan.x + renaming.position.x * self.zoom)
+                .top(self.pan.y + renaming.position.y * self.zoom)
+                .w(px(renaming.width.max(160.0) * self.zoom))
+                .bg(graph_cx.theme().background)
+                .on_key_down(graph_cx.listener(|this, e: &KeyDownEvent, window, cx| {
+                    if e.keystroke.key == "escape" {
+                        this.finish_rename(false, window, cx);
+                    }
+                }))
+                .child(Input::new(&renaming.input).small())
+        });
+
+        let selection_box = self.selection_box.map(|(start, end)| {
+            let ring = graph_cx.theme().ring;
+            div()
+                .absolute()
+                .left(start.x.min(end.x))
+                .top(start.y.min(end.y))
+                .w((end.x - start.x).abs())
+                .h((end.y - start.y).abs())
+                .border(px(1.0))
+                .border_color(ring)
+                .bg(ring.opacity(0.1))
+        });
+
+        // Outline while the graph has the keyboard focus
+        let focus_ring = self.focus_handle.is_focused(window).then(|| {
+            div()
+                .absolute()
+                .inset_0()
+                .border(px(1.0))
+                .border_color(graph_cx.theme().ring)
+        });
+
+        let graph_view = div()
             .size_full()
             .cursor(gpui::CursorStyle::Arrow)
+            .track_focus(&self.focus_handle)
+            .key_context(CONTEXT)
+            .on_action(graph_cx.listener(Self::on_toggle_collapse))
+            .on_action(graph_cx.listener(Self::on_toggle_pin))
+            .on_action(graph_cx.listener(Self::on_add_ecu))
+            .on_action(graph_cx.listener(Self::on_add_bus))
+            .on_action(graph_cx.listener(Self::on_rename_node))
+            .on_action(graph_cx.listener(Self::on_delete_node))
+            .on_action(graph_cx.listener(Self::on_undo))
+            .on_action(graph_cx.listener(Self::on_redo))
+            .on_action(graph_cx.listener(Self::on_select_left))
+            .on_action(graph_cx.listener(Self::on_select_right))
+            .on_action(graph_cx.listener(Self::on_select_up))
+            .on_action(graph_cx.listener(Self::on_select_down))
+            .on_action(graph_cx.listener(Self::on_follow_edge_left))
+            .on_action(graph_cx.listener(Self::on_follow_edge_right))
+            .on_action(graph_cx.listener(Self::on_follow_edge_up))
+            .on_action(graph_cx.listener(Self::on_follow_edge_down))
+            .on_action(graph_cx.listener(Self::on_select_next))
+            .on_action(graph_cx.listener(Self::on_select_previous))
+            .on_action(graph_cx.listener(Self::on_clear_selection))
+            .on_action(graph_cx.listener(Self::on_jump_to_source))
+            .on_action(graph_cx.listener(Self::on_zoom_in))
+            .on_action(graph_cx.listener(Self::on_zoom_out))
+            .on_action(graph_cx.listener(Self::on_reset_zoom))
+            .on_action(graph_cx.listener(Self::on_fit_view))
             // Background is transparent so parent can set the themed background
             .child(sim_canvas)
+            .children(transition_canvas)
             // Clicking selects node under cursor; shift adds to selection; clicking empty space starts panning
             .on_mouse_down(
                 gpui::MouseButton::Left,
-                graph_cx.listener(|this, e: &gpui::MouseDownEvent, _w, cx| {
+                graph_cx.listener(|this, e: &gpui::MouseDownEvent, window, cx| {
+                    window.focus(&this.focus_handle);
                     // Convert to container-local coordinates for hit testing
                     let cursor = point(
                         e.position.x - this.container_offset.x,
                         e.position.y - this.container_offset.y,
                     );
 
-                    // Ignore clicks in UI control regions (top-left controls panel, top-right play button)
-                    // Controls panel: top-left, approximately 280x50 area
-                    // Play button: top-right, approximately 40x40 area
+                    // Ignore clicks on the play button, top-right, approximately 40x40 area.
+                    // The controls panel keeps its clicks from reaching the canvas itself.
                     let container_width = this.container_size.width;
-                    let in_controls_panel = cursor.x < px(290.0) && cursor.y < px(60.0);
                     let in_play_button =
                         cursor.x > container_width - px(50.0) && cursor.y < px(50.0);
 
-                    if in_controls_panel || in_play_button {
+                    if in_play_button {
                         // Don't start panning or selecting when clicking on UI controls
                         return;
                     }
 
-                    let mut hit_index: Option<usize> = None;
-                    // Check each node using its actual width and height
-                    for (i, n) in this.nodes.iter().enumerate() {
-                        let (nx, ny, node_width, node_height) =
-                            cx.read_entity(n, |node, _| (node.x, node.y, node.width, node.height));
-                        let left = this.pan.x + nx * this.zoom;
-                        let top = this.pan.y + ny * this.zoom;
-                        let scaled_width = px(node_width) * this.zoom;
-                        let scaled_height = px(node_height) * this.zoom;
-                        if cursor.x >= left
-                            && cursor.x <= left + scaled_width
-                            && cursor.y >= top
-                            && cursor.y <= top + scaled_height
-                        {
-                            hit_index = Some(i);
-                            break;
-                        }
-                    }
-
-                    match hit_index {
-                        Some(i) => {
-                            let shift = e.modifiers.shift;
-                            if !shift {
-                                for n in &this.nodes {
-                                    cx.update_entity(n, |node, _| node.selected = false);
-                                }
+                    // A drag or pan may follow, undone as one step
+                    this.change_start = Some(this.layout_snapshot(cx));
+                    // Pressing a node of a multiple selection keeps it, to drag the nodes
+                    // together; released without a drag, the selection narrows to that node
+                    let hit = this.node_at(cursor, cx);
+                    let keep = !e.modifiers.shift
+                        && hit.is_some_and(|i| this.nodes[i].read(cx).selected)
+                        && this.nodes.iter().filter(|n| n.read(cx).selected).count() > 1;
+                    this.pending_select = None;
+                    this.is_dragging_nodes = false;
+                    let target = if keep {
+                        this.pending_select = Some(cursor);
+                        hit.map(|i| this.nodes[i].clone())
+                    } else {
+                        this.select_at(cursor, e.modifiers.shift, cx)
+                    };
+                    match target {
+                        Some(target) => {
+                            // Double-click collapses or expands the container under the cursor
+                            if e.click_count == 2 {
+                                let path = cx.read_entity(&target, |node, _| {
+                                    node.selected_child.clone().unwrap_or_default()
+                                });
+                                this.record_layout(cx);
+                                this.toggle_collapsed(&target, &path, cx);
                             }
-                            let target = this.nodes[i].clone();
-                            let (node_id, span) =
-                                cx.read_entity(&target, |node, _| (node.id, node.span));
-                            cx.update_entity(&target, |node, _| {
-                                node.selected = true;
-                            });
-                            cx.emit(NodeSelected { node_id, span });
+                        }
+                        // Shift-dragging on empty space drags out a selection rectangle
+                        None if e.modifiers.shift => {
+                            this.change_start = None;
+                            this.selection_box = Some((cursor, cursor));
                         }
                         None => {
                             // No node hit - start panning
@@ -1366,10 +3056,46 @@ impl Render for Graph {
                     cx.notify();
                 }),
             )
+            // Right click selects what the context menu acts on. This runs in the capture
+            // phase, before the context menu opens and takes the focus.
+            .capture_any_mouse_down(graph_cx.listener(
+                |this, e: &gpui::MouseDownEvent, window, cx| {
+                    if e.button != gpui::MouseButton::Right {
+                        return;
+                    }
+                    window.focus(&this.focus_handle);
+                    let cursor = point(
+                        e.position.x - this.container_offset.x,
+                        e.position.y - this.container_offset.y,
+                    );
+                    this.menu_position = Some(point(
+                        (cursor.x - this.pan.x) / this.zoom,
+                        (cursor.y - this.pan.y) / this.zoom,
+                    ));
+                    let selected = this
+                        .node_at(cursor, cx)
+                        .is_some_and(|i| this.nodes[i].read(cx).selected);
+                    // Keep a multiple selection when opening the menu on one of its nodes
+                    if !selected || e.modifiers.shift {
+                        this.select_at(cursor, e.modifiers.shift, cx);
+                    }
+                    cx.notify();
+                },
+            ))
             .on_mouse_up(
                 gpui::MouseButton::Left,
                 graph_cx.listener(|this, _e: &gpui::MouseUpEvent, _w, cx| {
+                    if this.is_panning {
+                        this.finish_change(cx);
+                        cx.emit(ViewChanged);
+                    }
                     this.is_panning = false;
+                    this.finish_selection_box(cx);
+                    if let Some(cursor) = this.pending_select.take()
+                        && !this.is_dragging_nodes
+                    {
+                        this.select_at(cursor, false, cx);
+                    }
                     cx.notify();
                 }),
             )
@@ -1379,9 +3105,17 @@ impl Render for Graph {
                     let dy = delta_px.y;
 
                     if dy != px(0.0) {
+                        let now = Instant::now();
+                        if this
+                            .last_scroll
+                            .is_none_or(|last| now.duration_since(last) > SCROLL_STEP)
+                        {
+                            this.record_layout(cx);
+                        }
+                        this.last_scroll = Some(now);
                         let factor = if dy > px(0.0) { 1.1 } else { 0.9 };
                         let old_zoom = this.zoom;
-                        let new_zoom = (old_zoom * factor).clamp(0.25, 4.0);
+                        let new_zoom = (old_zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
 
                         // Zoom toward cursor position by adjusting pan
                         // Convert window position to container-local position
@@ -1402,6 +3136,7 @@ impl Render for Graph {
                                 node.pan = pan;
                             });
                         }
+                        cx.emit(ViewChanged);
                         // ensure the graph re-renders so shared canvases reflect new zoom/pan
                         cx.notify();
                     }
@@ -1411,6 +3146,7 @@ impl Render for Graph {
             .on_mouse_down(
                 gpui::MouseButton::Middle,
                 graph_cx.listener(|this, e: &gpui::MouseDownEvent, _w, cx| {
+                    this.change_start = Some(this.layout_snapshot(cx));
                     this.is_panning = true;
                     this.pan_start = this.pan;
                     this.pan_start_pos = point(
@@ -1423,17 +3159,44 @@ impl Render for Graph {
             .on_mouse_up(
                 gpui::MouseButton::Middle,
                 graph_cx.listener(|this, _e: &gpui::MouseUpEvent, _w, cx| {
+                    if this.is_panning {
+                        this.finish_change(cx);
+                        cx.emit(ViewChanged);
+                    }
                     this.is_panning = false;
                     cx.notify();
                 }),
             )
             .on_mouse_move(graph_cx.listener(|this, e: &gpui::MouseMoveEvent, _w, cx| {
+                if let Some((_, end)) = &mut this.selection_box {
+                    if e.pressed_button == Some(gpui::MouseButton::Left) {
+                        *end = point(
+                            e.position.x - this.container_offset.x,
+                            e.position.y - this.container_offset.y,
+                        );
+                        cx.notify();
+                    } else {
+                        // Released outside the graph
+                        this.finish_selection_box(cx);
+                    }
+                    return;
+                }
+
                 // Stop panning if left mouse button is no longer pressed
                 if this.is_panning
                     && !e.pressed_button.is_some_and(|b| {
                         b == gpui::MouseButton::Left || b == gpui::MouseButton::Middle
                     })
                 {
+                    this.is_panning = false;
+                    this.finish_change(cx);
+                    cx.emit(ViewChanged);
+                    cx.notify();
+                    return;
+                }
+
+                // A drag from a port or a child started on the canvas, it does not pan
+                if this.is_panning && cx.has_active_drag() {
                     this.is_panning = false;
                     cx.notify();
                     return;
@@ -1461,7 +3224,10 @@ impl Render for Graph {
                 }
             }))
             .child(graph_canvas)
+            .children(selection_box)
+            .children(focus_ring)
             .child(controls_panel)
+            .children(stale_banner)
             .child({
                 // Play button - runs force simulation in Force mode, or applies dagre layout once in Dagre mode
                 let is_playing_force = self.playing && self.layout_mode == LayoutMode::Force;
@@ -1485,10 +3251,8 @@ impl Render for Graph {
                     .justify_center()
                     .text_size(px(12.0))
                     .child(div().text_color(button_text_color).child(
-                        if self.layout_mode == LayoutMode::Dagre
-                            || self.layout_mode == LayoutMode::ArciVis
-                        {
-                            "⟳" // Refresh/relayout icon for dagre and arcivis
+                        if self.layout_mode != LayoutMode::Force {
+                            "⟳" // Refresh/relayout icon for dagre, arcivis and bus
                         } else if self.playing {
                             "||" // Pause symbol (using ASCII for better visibility)
                         } else {
@@ -1499,6 +3263,7 @@ impl Render for Graph {
                         gpui::MouseButton::Left,
                         graph_cx.listener({
                             move |this, _e: &gpui::MouseDownEvent, _w, cx| {
+                                this.record_layout(cx);
                                 match this.layout_mode {
                                     LayoutMode::Force => {
                                         this.playing = !this.playing;
@@ -1511,11 +3276,118 @@ impl Render for Graph {
                                         // In ArciVis mode, clicking applies the layout once
                                         this.apply_arcivis_layout(cx);
                                     }
+                                    LayoutMode::Bus => this.apply_bus_layout(cx),
                                 }
+                                // Also when the simulation is paused, after moving the nodes
+                                cx.emit(LayoutChanged);
                                 cx.notify();
                             }
                         }),
                     )
             })
+            .context_menu(|menu, _window, _cx| {
+                menu.menu("Add ECU", Box::new(AddEcu))
+                    .menu("Add Bus", Box::new(AddBus))
+                    .separator()
+                    .menu("Rename", Box::new(RenameNode))
+                    .menu("Delete", Box::new(DeleteNode))
+            });
+
+        div()
+            .relative()
+            .size_full()
+            .child(graph_view)
+            .children(rename_editor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generators::utils::generate_nodes;
+
+    fn node(id: u64, key: &str, x: f32) -> GraphNode {
+        let mut node = generate_nodes(1).remove(0);
+        node.id = id;
+        node.key = key.to_string();
+        node.name = key.to_string();
+        node.x = px(x);
+        node.y = px(0.0);
+        node
+    }
+
+    fn graph(cx: &mut TestAppContext) -> Entity<Graph> {
+        cx.new(|cx| {
+            let nodes = vec![node(1, "a", 0.0), node(2, "b", 200.0), node(3, "c", 400.0)];
+            let edges = vec![GraphEdge::new(0, 1), GraphEdge::new(1, 2)];
+            Graph::new(cx, nodes, edges, 30, 0.9)
+        })
+    }
+
+    fn keys(graph: &Entity<Graph>, cx: &mut TestAppContext) -> Vec<String> {
+        graph.read_with(cx, |graph, cx| {
+            graph.nodes.iter().map(|n| n.read(cx).key.clone()).collect()
+        })
+    }
+
+    #[gpui::test]
+    fn test_update_model_keeps_nodes(cx: &mut TestAppContext) {
+        let graph = graph(cx);
+        let a = graph.read_with(cx, |graph, _| graph.nodes[0].clone());
+        // Moved by the user, the model still has the old position
+        a.update(cx, |a, _| a.x = px(50.0));
+
+        graph.update(cx, |graph, cx| {
+            let nodes = vec![node(0, "a", 0.0), node(0, "b", 200.0), node(0, "c", 400.0)];
+            let edges = vec![GraphEdge::new(0, 1), GraphEdge::new(1, 2)];
+            graph.update_model(nodes, edges, cx);
+        });
+
+        graph.read_with(cx, |graph, cx| {
+            assert_eq!(graph.nodes[0].entity_id(), a.entity_id());
+            let a = graph.nodes[0].read(cx);
+            assert_eq!((a.id, a.x), (1, px(50.0)));
+        });
+    }
+
+    #[gpui::test]
+    fn test_update_model_renamed_key(cx: &mut TestAppContext) {
+        let graph = graph(cx);
+        let b = graph.read_with(cx, |graph, _| graph.nodes[1].clone());
+
+        graph.update(cx, |graph, cx| {
+            let nodes = vec![node(0, "a", 0.0), node(0, "b2", 200.0), node(0, "c", 400.0)];
+            let edges = vec![GraphEdge::new(0, 1), GraphEdge::new(1, 2)];
+            graph.update_model(nodes, edges, cx);
+        });
+
+        assert_eq!(keys(&graph, cx), ["a", "b2", "c"]);
+        graph.read_with(cx, |graph, cx| {
+            // A different node, not the old one under a new name
+            assert_ne!(graph.nodes[1].entity_id(), b.entity_id());
+            assert_eq!(graph.nodes[1].read(cx).id, 4);
+            assert_eq!(graph.node_subscriptions.len(), 3);
+            assert!(!graph.node_subscriptions.contains_key(&b.entity_id()));
+        });
+    }
+
+    #[gpui::test]
+    fn test_update_model_drops_removed(cx: &mut TestAppContext) {
+        let graph = graph(cx);
+
+        graph.update(cx, |graph, cx| {
+            let nodes = vec![node(0, "a", 0.0), node(0, "c", 400.0)];
+            let edges = vec![GraphEdge::new(0, 1)];
+            graph.update_model(nodes, edges, cx);
+        });
+
+        assert_eq!(keys(&graph, cx), ["a", "c"]);
+        graph.read_with(cx, |graph, cx| {
+            assert_eq!(graph.edges.len(), 1);
+            let edge = &graph.edges[0];
+            assert_eq!(graph.nodes[edge.source].read(cx).key, "a");
+            assert_eq!(graph.nodes[edge.target].read(cx).key, "c");
+            assert_eq!(graph.node_subscriptions.len(), 2);
+        });
     }
 }
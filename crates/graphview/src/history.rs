@@ -0,0 +1,168 @@
+//! Undo and redo of changes made in the graph
+
+use crate::graph::LayoutSnapshot;
+
+/// Number of steps kept for undo
+const LIMIT: usize = 100;
+
+/// One change that can be undone
+#[derive(Clone, Debug)]
+pub enum Step {
+    /// The arrangement before a move, layout run or view change
+    Layout(LayoutSnapshot),
+    /// An edit of the model, reverted and repeated by the owner of the graph
+    Model,
+}
+
+#[derive(Debug, Default)]
+pub struct History {
+    undo: Vec<Step>,
+    redo: Vec<Step>,
+}
+
+impl History {
+    /// Record a change; anything undone before can no longer be redone
+    pub fn push(&mut self, step: Step) {
+        if self.undo.len() == LIMIT {
+            self.undo.remove(0);
+        }
+        self.undo.push(step);
+        self.redo.clear();
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    /// The step to undo, with `current` kept to redo it
+    pub fn undo(&mut self, current: LayoutSnapshot) -> Option<Step> {
+        Self::take(&mut self.undo, &mut self.redo, current)
+    }
+
+    /// The step to redo, with `current` kept to undo it again
+    pub fn redo(&mut self, current: LayoutSnapshot) -> Option<Step> {
+        Self::take(&mut self.redo, &mut self.undo, current)
+    }
+
+    /// Put back a step its owner could not undo, so it is the next one to undo again
+    pub fn reject_undo(&mut self) {
+        if let Some(step) = self.redo.pop() {
+            self.undo.push(step);
+        }
+    }
+
+    /// Put back a step its owner could not redo, so it is the next one to redo again
+    pub fn reject_redo(&mut self) {
+        if let Some(step) = self.undo.pop() {
+            self.redo.push(step);
+        }
+    }
+
+    /// Move the last step of `from` to `to`. Arrangements equal to the current one would
+    /// change nothing and are dropped.
+    fn take(from: &mut Vec<Step>, to: &mut Vec<Step>, current: LayoutSnapshot) -> Option<Step> {
+        loop {
+            let step = from.pop()?;
+            match &step {
+                Step::Layout(layout) if *layout == current => continue,
+                Step::Layout(_) => to.push(Step::Layout(current)),
+                Step::Model => to.push(Step::Model),
+            }
+            return Some(step);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An arrangement told apart by its zoom
+    fn layout(zoom: f32) -> LayoutSnapshot {
+        LayoutSnapshot {
+            zoom,
+            ..Default::default()
+        }
+    }
+
+    fn zoom(step: Option<Step>) -> Option<f32> {
+        match step? {
+            Step::Layout(layout) => Some(layout.zoom),
+            Step::Model => None,
+        }
+    }
+
+    #[test]
+    fn test_undo_redo_layout() {
+        let mut history = History::default();
+        history.push(Step::Layout(layout(1.0)));
+        history.push(Step::Layout(layout(2.0)));
+
+        assert_eq!(zoom(history.undo(layout(3.0))), Some(2.0));
+        assert_eq!(zoom(history.undo(layout(2.0))), Some(1.0));
+        assert!(history.undo(layout(1.0)).is_none());
+
+        assert_eq!(zoom(history.redo(layout(1.0))), Some(2.0));
+        assert_eq!(zoom(history.redo(layout(2.0))), Some(3.0));
+        assert!(history.redo(layout(3.0)).is_none());
+    }
+
+    #[test]
+    fn test_undo_skips_unchanged_layout() {
+        let mut history = History::default();
+        history.push(Step::Layout(layout(1.0)));
+        history.push(Step::Layout(layout(2.0)));
+        assert_eq!(zoom(history.undo(layout(2.0))), Some(1.0));
+    }
+
+    #[test]
+    fn test_undo_redo_model() {
+        let mut history = History::default();
+        history.push(Step::Layout(layout(1.0)));
+        history.push(Step::Model);
+
+        assert!(matches!(history.undo(layout(2.0)), Some(Step::Model)));
+        assert_eq!(zoom(history.undo(layout(2.0))), Some(1.0));
+        assert_eq!(zoom(history.redo(layout(1.0))), Some(2.0));
+        assert!(matches!(history.redo(layout(2.0)), Some(Step::Model)));
+    }
+
+    #[test]
+    fn test_push_clears_redo() {
+        let mut history = History::default();
+        history.push(Step::Layout(layout(1.0)));
+        assert!(history.undo(layout(2.0)).is_some());
+        history.push(Step::Model);
+        assert!(history.redo(layout(2.0)).is_none());
+        assert!(matches!(history.undo(layout(2.0)), Some(Step::Model)));
+    }
+
+    #[test]
+    fn test_reject() {
+        let mut history = History::default();
+        history.push(Step::Model);
+
+        assert!(history.undo(layout(1.0)).is_some());
+        history.reject_undo();
+        assert!(history.redo(layout(1.0)).is_none());
+        assert!(history.undo(layout(1.0)).is_some());
+
+        assert!(history.redo(layout(1.0)).is_some());
+        history.reject_redo();
+        assert!(matches!(history.redo(layout(1.0)), Some(Step::Model)));
+    }
+
+    #[test]
+    fn test_limit() {
+        let mut history = History::default();
+        for i in 0..=LIMIT {
+            history.push(Step::Layout(layout(i as f32)));
+        }
+        let mut steps = 0;
+        while history.undo(layout(-1.0)).is_some() {
+            steps += 1;
+        }
+        assert_eq!(steps, LIMIT);
+    }
+}
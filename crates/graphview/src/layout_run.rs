@@ -0,0 +1,192 @@
+//! Layout runs on the background executor
+//!
+//! A run works on a snapshot of the node sizes, positions and edges taken when it starts,
+//! so the window stays responsive while it computes. The graph applies the result in one
+//! step once it is done, or drops it when the graph changed in the meantime.
+
+use crate::bus;
+use crate::dagre::{self, DagreSettings};
+use arcivis_layout::{ArciVisLayout, Node, Port, PortType, Position, Size};
+use tracing::debug;
+
+/// Layout algorithm a run carries out
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LayoutRun {
+    /// Dagre hierarchical layout, moving the nodes
+    Dagre(DagreSettings),
+    /// ArciVis layout, moving the nodes and routing the edges
+    ArciVis,
+    /// ArciVis edge routing around the nodes where they are
+    ArciVisRoutes,
+    /// Buses as bars above the ECUs tapping them
+    Bus,
+}
+
+/// A node as seen by a layout run
+#[derive(Clone, Debug)]
+pub struct LayoutNode {
+    pub name: String,
+    pub width: f32,
+    pub height: f32,
+    pub x: f32,
+    pub y: f32,
+    /// Drawn as a bar in the bus layout
+    pub bus: bool,
+}
+
+/// Snapshot of the graph for a layout run
+#[derive(Clone, Debug, Default)]
+pub struct LayoutInput {
+    pub nodes: Vec<LayoutNode>,
+    /// Routable edges: index in the graph's edges, source and target node
+    pub edges: Vec<(usize, usize, usize)>,
+}
+
+/// Result of a layout run
+#[derive(Clone, Debug, Default)]
+pub struct LayoutOutput {
+    /// New position of every node, in node order; `None` keeps the nodes where they are
+    pub positions: Option<Vec<(f32, f32)>>,
+    /// Routes by index in the graph's edges
+    pub paths: Vec<(usize, Vec<(f32, f32)>)>,
+    /// Width of the bar each bus is drawn as, by node index
+    pub bars: Vec<(usize, f32)>,
+}
+
+impl LayoutRun {
+    pub fn run(self, input: &LayoutInput) -> LayoutOutput {
+        match self {
+            LayoutRun::Dagre(settings) => dagre::layout(input, &settings),
+            LayoutRun::ArciVis => arcivis(input),
+            LayoutRun::ArciVisRoutes => arcivis_routes(input),
+            LayoutRun::Bus => bus::layout(input),
+        }
+    }
+}
+
+fn arcivis(input: &LayoutInput) -> LayoutOutput {
+    // Nodes start at the origin, the layout sets proper positions
+    let layout_nodes = arcivis_nodes(input, false);
+    let layout = ArciVisLayout::default();
+    let result = layout.layout(layout_nodes, arcivis_edges(input));
+    debug!("ArciVis layout placed {} nodes", result.nodes.len());
+
+    // Nodes missing from the result keep their position
+    let positions = input
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(i, node)| match result.nodes.get(i) {
+            Some(placed) => (placed.position.x as f32, placed.position.y as f32),
+            None => (node.x, node.y),
+        })
+        .collect();
+    let paths = input
+        .edges
+        .iter()
+        .zip(&result.edges)
+        .map(|(&(i, _, _), edge)| {
+            (
+                i,
+                edge.path.iter().map(|p| (p.x as f32, p.y as f32)).collect(),
+            )
+        })
+        .collect();
+    LayoutOutput {
+        positions: Some(positions),
+        paths,
+        bars: Vec::new(),
+    }
+}
+
+fn arcivis_routes(input: &LayoutInput) -> LayoutOutput {
+    // Use CURRENT node positions, don't let layout reposition
+    let layout_nodes = arcivis_nodes(input, true);
+    let layout = ArciVisLayout::default();
+    let routed_edges = layout.route_edges_only(&layout_nodes, &arcivis_edges(input));
+
+    // Only update edge paths, don't move nodes
+    let paths = input
+        .edges
+        .iter()
+        .zip(&routed_edges)
+        .map(|(&(i, _, _), edge)| {
+            (
+                i,
+                edge.path.iter().map(|p| (p.x as f32, p.y as f32)).collect(),
+            )
+        })
+        .collect();
+    LayoutOutput {
+        positions: None,
+        paths,
+        bars: Vec::new(),
+    }
+}
+
+/// Nodes for the ArciVis layout, at their current position or at the origin
+fn arcivis_nodes(input: &LayoutInput, keep_positions: bool) -> Vec<Node> {
+    input
+        .nodes
+        .iter()
+        .map(|node| {
+            // Define ports: left (input) and right (output)
+            let header_height = 28.0f64;
+            let port_size = 10.0f64;
+            let port_y = (header_height - port_size) / 2.0; // Center port vertically in header
+            let ports = vec![
+                Port {
+                    position: Position {
+                        x: -port_size / 2.0,
+                        y: port_y,
+                    }, // Left port centered on edge
+                    size: Size {
+                        width: port_size,
+                        height: port_size,
+                    },
+                    port_type: PortType::Input,
+                    id: None,
+                },
+                Port {
+                    position: Position {
+                        x: node.width as f64 - port_size / 2.0,
+                        y: port_y,
+                    }, // Right port centered on edge
+                    size: Size {
+                        width: port_size,
+                        height: port_size,
+                    },
+                    port_type: PortType::Output,
+                    id: None,
+                },
+            ];
+            let position = if keep_positions {
+                Position {
+                    x: node.x as f64,
+                    y: node.y as f64,
+                }
+            } else {
+                Position { x: 0.0, y: 0.0 }
+            };
+            Node {
+                id: node.name.clone(),
+                size: Size {
+                    width: node.width as f64,
+                    height: node.height as f64,
+                },
+                position,
+                ports,
+                attributes: vec![],
+            }
+        })
+        .collect()
+}
+
+/// Edges for the ArciVis layout, between the nodes' single input and output ports
+fn arcivis_edges(input: &LayoutInput) -> Vec<(usize, usize, Option<usize>, Option<usize>)> {
+    input
+        .edges
+        .iter()
+        .map(|&(_, source, target)| (source, target, Some(0), Some(0)))
+        .collect()
+}
@@ -1,14 +1,41 @@
+pub use crate::child_layout::ChildLayout;
+pub use crate::dagre::DagreAlign;
+pub use crate::dagre::DagreRanker;
+pub use crate::dagre::DagreSettings;
+pub use crate::dagre::RankDirection;
+pub use crate::edge::EdgeKind;
 pub use crate::edge::GraphEdge;
 pub use crate::generators::utils::generate_nodes;
 pub use crate::generators::watts_strogatz::generate_watts_strogatz_graph;
 pub use crate::graph::EdgeRouting;
 pub use crate::graph::Graph;
+pub use crate::graph::JumpToSpan;
+pub use crate::graph::LayoutChanged;
 pub use crate::graph::LayoutMode;
+pub use crate::graph::LayoutSnapshot;
+pub use crate::graph::ModelEdit;
+pub use crate::graph::ModelHistory;
+pub use crate::graph::NodeLayout;
 pub use crate::graph::NodeMoved;
 pub use crate::graph::NodeSelected;
+pub use crate::graph::SelectionChanged;
+pub use crate::graph::StaleState;
+pub use crate::graph::ToggleCollapse;
+pub use crate::graph::TogglePin;
+pub use crate::graph::ViewChanged;
+pub use crate::graph::init;
+pub use crate::layout_run::LayoutRun;
+pub use crate::node::ChildPath;
 pub use crate::node::GraphNode;
 pub use crate::node::NodeChild;
+pub use crate::node::NodePort;
+pub use crate::node::PortKind;
+mod bus;
+pub mod child_layout;
+pub mod dagre;
 pub mod edge;
 pub mod generators;
 pub mod graph;
+mod history;
+pub mod layout_run;
 pub mod node;
@@ -1,19 +1,102 @@
-use crate::NodeMoved;
+use crate::child_layout::{
+    ChildLayout, HEADER_HEIGHT, LABEL_HEIGHT, PADDING, PORT_ROW_HEIGHT, layout_node,
+};
+use crate::{ModelEdit, NodeMoved};
 use gpui::div;
+use gpui::prelude::FluentBuilder;
 use gpui::*;
 use gpui_component::ActiveTheme;
+use gpui_component::tooltip::Tooltip;
 
-/// Child element inside a node (partition or swc)
-#[derive(Clone, Debug)]
+/// Child element inside a node (a container such as a partition, a swc, or an interface member)
+#[derive(Clone, Debug, PartialEq)]
 pub struct NodeChild {
     pub name: String,
-    pub kind: String, // "partition" or "swc"
+    pub kind: String, // e.g. "vm", "partition", "composition", "swc", "method", "event"
     pub children: Vec<NodeChild>,
+    /// Ports shown on the border of the child (only used for swc)
+    pub ports: Vec<NodePort>,
+    /// Byte range in the source document for this child (start, end)
+    pub span: Option<(usize, usize)>,
+    /// Hide the nested children and only show the label
+    pub collapsed: bool,
+    /// Attributes from the model shown when hovering the child, e.g. `("period_ms", "10")`
+    pub details: Vec<(String, String)>,
+}
+
+/// Address of a nested child: the index at each level, starting with the node's children
+pub type ChildPath = Vec<usize>;
+
+/// Direction of a port on a child element
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PortKind {
+    /// Receives data, drawn on the left border
+    Input,
+    /// Sends data, drawn on the right border
+    Output,
+    /// Offers a service, drawn as a round marker on the right border
+    Provided,
+    /// Consumes a service, drawn as a hollow round marker on the left border
+    Required,
+}
+
+impl PortKind {
+    /// Whether the port is drawn on the left (incoming) border
+    pub fn is_incoming(&self) -> bool {
+        matches!(self, Self::Input | Self::Required)
+    }
+}
+
+/// Port marker on a child element (e.g., `input_port "RPM" signal="RPM"`)
+#[derive(Clone, Debug, PartialEq)]
+pub struct NodePort {
+    pub name: String,
+    pub kind: PortKind,
+    /// Signal carried by the port, or the service interface for service ports
+    pub signal: Option<String>,
+}
+
+impl NodeChild {
+    /// Members (operations and fields) are listed as text lines instead of nested boxes
+    pub fn is_member(&self) -> bool {
+        matches!(self.kind.as_str(), "method" | "event" | "field")
+    }
+
+    /// Containers hold nested elements, swcs and members do not
+    pub fn is_container(&self) -> bool {
+        !self.is_member() && self.kind != "swc"
+    }
+
+    /// Containers with nested children can be collapsed, swcs and members cannot
+    pub fn is_collapsible(&self) -> bool {
+        !self.children.is_empty() && !self.is_member()
+    }
+
+    /// Nested children that are shown, none while collapsed
+    pub fn visible_children(&self) -> &[NodeChild] {
+        if self.collapsed { &[] } else { &self.children }
+    }
+
+    /// Carry the collapsed state over to updated children, matched by kind and name
+    pub fn keep_collapsed(old: &[NodeChild], new: &mut [NodeChild]) {
+        for child in new {
+            let Some(previous) = old
+                .iter()
+                .find(|o| o.kind == child.kind && o.name == child.name)
+            else {
+                continue;
+            };
+            child.collapsed = previous.collapsed && child.is_collapsible();
+            Self::keep_collapsed(&previous.children, &mut child.children);
+        }
+    }
 }
 
 // Simple draggable node with label
 pub struct GraphNode {
     pub id: u64,
+    /// Stable identity used to match nodes across model updates (e.g., qualified KDL name)
+    pub key: String,
     pub name: String,
     pub node_type: String, // "ecu", "bus", "interface", etc.
     pub x: Pixels,
@@ -23,16 +106,29 @@ pub struct GraphNode {
     pub zoom: f32,
     pub pan: Point<Pixels>,
     pub selected: bool,
+    /// Nested child selected within this node, narrows edge highlighting to its edges
+    pub selected_child: Option<ChildPath>,
+    /// Hide the children and only show the header
+    pub collapsed: bool,
+    /// Placed by hand: layouts and the force simulation leave the node where it is
+    pub pinned: bool,
+    /// Position from a layout hint in the model. Layouts move the node like any other, the
+    /// hint only takes effect again when it changes.
+    pub hint: Option<(f32, f32)>,
     // Container offset in window coordinates (set by Graph during render)
     pub container_offset: Point<Pixels>,
     // Actual node width (calculated based on text content)
     pub width: f32,
     // Actual node height (calculated based on children)
     pub height: f32,
-    // Child elements (partitions, swcs)
+    // Child elements (containers, swcs or interface members), nested to any depth
     pub children: Vec<NodeChild>,
+    /// Width of the bar a bus is drawn as in the bus layout, spanning the ECUs tapping it
+    pub bar_width: Option<f32>,
     /// Byte range in the source document for this node (start, end)
     pub span: Option<(usize, usize)>,
+    /// Attributes from the model shown when hovering the name, e.g. `("vendor", "Bosch")`
+    pub details: Vec<(String, String)>,
     /// Reference to the parent graph entity for event emission
     pub graph_entity: Option<Entity<super::Graph>>,
 }
@@ -42,80 +138,396 @@ impl EventEmitter<NodeMoved> for GraphNode {}
 impl GraphNode {
     /// Estimate node size based on name, type, and children (static version for pre-creation estimation)
     pub fn estimate_node_size(name: &str, node_type: &str, children: &[NodeChild]) -> (f32, f32) {
-        let base_width = 120.0f32;
-        let header_height = 28.0f32;
-        let char_width = 7.2f32;
-        let padding = 24.0f32;
-
-        // Width from name and type
-        let name_width = name.len() as f32 * char_width + padding;
-        let type_width = node_type.len() as f32 * 6.0 + 40.0;
-        let mut content_width = name_width.max(type_width).max(base_width);
-
-        if children.is_empty() {
-            return (content_width, header_height);
+        let (width, height, _) = layout_node(name, node_type, children);
+        (width, height)
+    }
+
+    /// Remaining selection path below the child at `index`, if the selection lies within it
+    fn selected_within(selected: Option<&[usize]>, index: usize) -> Option<&[usize]> {
+        match selected?.split_first()? {
+            (first, rest) if *first == index => Some(rest),
+            _ => None,
         }
+    }
 
-        // Estimate height: header + partitions stacked vertically
-        let mut total_child_height = 0.0f32;
-        let mut max_partition_width = 0.0f32;
+    /// Render the port markers of a swc: incoming ports on the left border, outgoing on the right
+    fn render_ports(ports: &[NodePort], zoom: f32, text_color: Hsla, border_color: Hsla) -> Div {
+        let port_size = 6.0f32;
+        // Markers sit on the border, i.e. shifted out by the child padding plus half their size
+        let marker_shift = 4.0 + port_size / 2.0;
+
+        let port_row = |port: &NodePort| {
+            let marker = div()
+                .size(px(port_size * zoom))
+                .flex_none()
+                .border(px(1.0))
+                .border_color(border_color);
+            // Signal ports are squares, service ports are round (hollow when required)
+            let marker = match port.kind {
+                PortKind::Input => marker.bg(rgb(0x4488ff)),
+                PortKind::Output => marker.bg(rgb(0xff8844)),
+                PortKind::Provided => marker.rounded_full().bg(rgb(0xaa88ee)),
+                PortKind::Required => marker
+                    .rounded_full()
+                    .bg(rgb(0x3a5a3a))
+                    .border_color(rgb(0xaa88ee)),
+            };
+            let marker = if port.kind.is_incoming() {
+                marker.ml(px(-marker_shift * zoom))
+            } else {
+                marker.mr(px(-marker_shift * zoom))
+            };
+            let label = div()
+                .text_size(px(8.0 * zoom))
+                .text_color(text_color)
+                .child(port.name.clone());
+            let row = div()
+                .h(px(PORT_ROW_HEIGHT * zoom))
+                .flex()
+                .items_center()
+                .gap(px(3.0 * zoom));
+            if port.kind.is_incoming() {
+                row.child(marker).child(label)
+            } else {
+                row.child(label).child(marker)
+            }
+        };
+        let column = |incoming: bool| {
+            div()
+                .flex()
+                .flex_col()
+                .when(!incoming, |this| this.items_end())
+                .children(
+                    ports
+                        .iter()
+                        .filter(|p| p.kind.is_incoming() == incoming)
+                        .map(port_row),
+                )
+        };
 
-        for child in children {
-            let swc_count = child.children.len().max(1);
-            let partition_height = 40.0 + (swc_count as f32 * 45.0);
-            total_child_height += partition_height + 8.0;
+        div()
+            .flex()
+            .justify_between()
+            .gap(px(8.0 * zoom))
+            .child(column(true))
+            .child(column(false))
+    }
 
-            let partition_name_width = child.name.len() as f32 * 6.0 + 50.0;
-            let max_swc_width = child
-                .children
-                .iter()
-                .map(|s| s.name.len() as f32 * 6.0 + 50.0)
-                .max_by(|a, b| a.partial_cmp(b).unwrap())
-                .unwrap_or(60.0);
-            let partition_width = partition_name_width.max(max_swc_width * 2.0 + 20.0);
-            max_partition_width = max_partition_width.max(partition_width);
+    /// Estimate node dimensions for hit testing (conservative/larger estimate)
+    pub fn estimate_dimensions(&self) -> (f32, f32) {
+        let (width, height) =
+            Self::estimate_node_size(&self.name, &self.node_type, self.visible_children());
+        (self.bar_width.unwrap_or(width).max(width), height)
+    }
+
+    /// Children that are shown, none while collapsed
+    fn visible_children(&self) -> &[NodeChild] {
+        if self.collapsed { &[] } else { &self.children }
+    }
+
+    /// Layout of the visible children relative to the node origin
+    pub fn child_layouts(&self) -> Vec<ChildLayout> {
+        layout_node(&self.name, &self.node_type, self.visible_children()).2
+    }
+
+    /// Path of the innermost child at a point relative to the node origin (unzoomed)
+    pub fn child_path_at(&self, x: f32, y: f32) -> Option<ChildPath> {
+        ChildLayout::hit(&self.child_layouts(), x, y)
+    }
+
+    /// Path of the innermost child whose source span contains the byte offset
+    pub fn child_path_at_offset(&self, offset: usize) -> Option<ChildPath> {
+        fn find(children: &[NodeChild], offset: usize, path: &mut ChildPath) -> bool {
+            for (index, child) in children.iter().enumerate() {
+                if child.span.is_some_and(|(s, e)| s <= offset && offset <= e) {
+                    path.push(index);
+                    // Stop at collapsed children, their content is not shown
+                    find(child.visible_children(), offset, path);
+                    return true;
+                }
+            }
+            false
         }
+        let mut path = Vec::new();
+        find(&self.children, offset, &mut path).then_some(path)
+    }
 
-        content_width = content_width.max(max_partition_width + 16.0);
-        let node_height = header_height + total_child_height + 12.0;
+    /// The child at `path`
+    pub fn child(&self, path: &[usize]) -> Option<&NodeChild> {
+        let (first, rest) = path.split_first()?;
+        rest.iter()
+            .try_fold(self.children.get(*first)?, |child, &index| {
+                child.children.get(index)
+            })
+    }
 
-        (content_width, node_height)
+    /// Source span of the child at `path`
+    pub fn child_span(&self, path: &[usize]) -> Option<(usize, usize)> {
+        self.child(path)?.span
     }
 
-    /// Estimate node dimensions for hit testing (conservative/larger estimate)
-    pub fn estimate_dimensions(&self) -> (f32, f32) {
-        Self::estimate_node_size(&self.name, &self.node_type, &self.children)
+    /// Source span of the container a child dragged from `span` is dropped into, at a point
+    /// relative to the node origin: the innermost container there, or the node itself.
+    /// `None` when the child would be dropped into itself or where it already is.
+    fn drop_target(&self, span: (usize, usize), x: f32, y: f32) -> Option<(usize, usize)> {
+        let mut path = self.child_path_at(x, y).unwrap_or_default();
+        while let Some(child) = self.child(&path)
+            && !child.is_container()
+        {
+            path.pop();
+        }
+        let target = if path.is_empty() {
+            // Of the nodes themselves only ECUs hold elements
+            self.span.filter(|_| self.node_type == "ecu")?
+        } else {
+            self.child(&path)?.span?
+        };
+        let into_itself = span.0 <= target.0 && target.1 <= span.1;
+        let unchanged = self
+            .child_path_at_offset(span.0)
+            .is_some_and(|current| current[..current.len() - 1] == path[..]);
+        (!into_itself && !unchanged).then_some(target)
+    }
+
+    /// Rendered bounds (x, y, width, height) of a visible child relative to the node origin
+    pub fn child_bounds(&self, path: &[usize]) -> Option<(f32, f32, f32, f32)> {
+        let layouts = self.child_layouts();
+        let mut level = layouts.as_slice();
+        let mut bounds = None;
+        let (mut x, mut y) = (0.0, 0.0);
+        for &index in path {
+            let layout = level.get(index)?;
+            x += layout.x;
+            y += layout.y;
+            bounds = Some((x, y, layout.width, layout.height));
+            level = &layout.children;
+        }
+        bounds
+    }
+
+    /// Graph position where an edge attaches: the right (outgoing) or left (incoming) border
+    /// of the child at `path`, or the node's own port when there is no such visible child
+    pub fn edge_anchor(&self, path: Option<&[usize]>, outgoing: bool) -> Point<Pixels> {
+        match path.and_then(|path| self.child_bounds(path)) {
+            Some((x, y, width, height)) => {
+                // Attach at the child's label line so boxes with many children stay readable
+                let anchor_y = y + (PADDING + LABEL_HEIGHT).min(height) / 2.0;
+                let anchor_x = if outgoing { x + width } else { x };
+                point(self.x + px(anchor_x), self.y + px(anchor_y))
+            }
+            None => {
+                let anchor_x = if outgoing { px(self.width) } else { px(0.0) };
+                point(self.x + anchor_x, self.y + px(HEADER_HEIGHT / 2.0))
+            }
+        }
+    }
+
+    /// The innermost shown child on the way to `path`, i.e. where an edge to a hidden
+    /// descendant attaches (`None` for the node itself)
+    pub fn visible_path(&self, path: Option<&[usize]>) -> Option<ChildPath> {
+        let mut level = self.visible_children();
+        let mut visible = Vec::new();
+        for &index in path? {
+            let Some(child) = level.get(index) else {
+                break;
+            };
+            visible.push(index);
+            level = child.visible_children();
+        }
+        (!visible.is_empty()).then_some(visible)
+    }
+
+    /// Path of the innermost collapsible container on the way to `path`
+    /// (empty for the node itself)
+    pub fn collapsible_path(&self, path: &[usize]) -> ChildPath {
+        let mut level = self.children.as_slice();
+        let mut collapsible = Vec::new();
+        for (depth, &index) in path.iter().enumerate() {
+            let Some(child) = level.get(index) else {
+                break;
+            };
+            if child.is_collapsible() {
+                collapsible = path[..=depth].to_vec();
+            }
+            level = &child.children;
+        }
+        collapsible
+    }
+
+    /// Whether an edge endpoint at `path` belongs to the current selection
+    pub fn is_endpoint_selected(&self, path: Option<&[usize]>) -> bool {
+        if !self.selected {
+            return false;
+        }
+        match (&self.selected_child, path) {
+            (None, _) => true,
+            // The selected child covers the endpoint itself and everything nested in it
+            (Some(selected), Some(path)) => path.starts_with(selected),
+            (Some(_), None) => false,
+        }
+    }
+
+    /// Collapse or expand the container at `path` (the node itself if empty) and update the
+    /// node size. Returns false if there is nothing to collapse.
+    pub fn toggle_collapsed_at(&mut self, path: &[usize]) -> bool {
+        match path.split_first() {
+            None if self.children.is_empty() => return false,
+            None => {
+                self.collapsed = !self.collapsed;
+                if self.collapsed {
+                    self.selected_child = None;
+                }
+            }
+            Some((first, rest)) => {
+                let mut child = self.children.get_mut(*first);
+                for &index in rest {
+                    child = child.and_then(|c| c.children.get_mut(index));
+                }
+                let Some(child) = child else {
+                    return false;
+                };
+                if !child.is_collapsible() {
+                    return false;
+                }
+                child.collapsed = !child.collapsed;
+                // A selection inside the collapsed container moves up to the container
+                if child.collapsed
+                    && let Some(selected) = &mut self.selected_child
+                    && selected.starts_with(path)
+                {
+                    selected.truncate(path.len());
+                }
+            }
+        }
+        (self.width, self.height) = self.estimate_dimensions();
+        true
+    }
+
+    /// Collapse or expand the node and let the graph lay out again
+    pub fn toggle_collapsed(&mut self, cx: &mut Context<Self>) {
+        if !self.toggle_collapsed_at(&[]) {
+            return;
+        }
+        if let Some(graph_entity) = &self.graph_entity {
+            cx.update_entity(graph_entity, |graph, cx| {
+                graph.handle_node_resized(cx);
+            });
+        }
+        cx.notify();
+    }
+
+    /// Name paths (e.g. `["Hypervisor", "VM1"]`) of the collapsed nested children
+    pub fn collapsed_children(&self) -> Vec<Vec<String>> {
+        fn collect(children: &[NodeChild], prefix: &mut Vec<String>, out: &mut Vec<Vec<String>>) {
+            for child in children {
+                prefix.push(child.name.clone());
+                if child.collapsed {
+                    out.push(prefix.clone());
+                }
+                collect(&child.children, prefix, out);
+                prefix.pop();
+            }
+        }
+        let mut paths = Vec::new();
+        collect(&self.children, &mut Vec::new(), &mut paths);
+        paths
     }
 
-    /// Render a child element (partition or swc) recursively
-    fn render_child(child: &NodeChild, zoom: f32, text_color: Hsla, border_color: Hsla) -> Div {
-        let char_width = 6.0f32;
-        let padding = 16.0f32;
-        let child_width = (child.name.len() as f32 * char_width + padding).max(60.0);
+    /// Collapse exactly the nested children at the given name paths and resize the node
+    pub fn set_collapsed_children(&mut self, paths: &[Vec<String>]) {
+        fn apply(children: &mut [NodeChild], prefix: &mut Vec<String>, paths: &[Vec<String>]) {
+            for child in children {
+                prefix.push(child.name.clone());
+                child.collapsed = child.is_collapsible() && paths.contains(prefix);
+                apply(&mut child.children, prefix, paths);
+                prefix.pop();
+            }
+        }
+        apply(&mut self.children, &mut Vec::new(), paths);
+        self.selected_child = None;
+        (self.width, self.height) = self.estimate_dimensions();
+    }
+
+    /// Render an interface member as a text line (e.g., `EngineSpeed(): rpm: uint16`)
+    fn render_member(member: &NodeChild, zoom: f32, text_color: Hsla) -> Div {
+        let kind_color = match member.kind.as_str() {
+            "event" => rgb(0xffcc88),
+            _ => rgb(0x88aacc),
+        };
+        div()
+            .size_full()
+            .px(px(4.0 * zoom))
+            .flex()
+            .flex_col()
+            .child(
+                div()
+                    .flex()
+                    .gap(px(4.0 * zoom))
+                    .text_size(px(10.0 * zoom))
+                    .child(
+                        div()
+                            .text_color(kind_color)
+                            .child(if member.kind == "event" { "⚡" } else { "+" }),
+                    )
+                    .child(div().text_color(text_color).child(member.name.clone())),
+            )
+            .children(member.children.iter().map(|field| {
+                div()
+                    .pl(px(12.0 * zoom))
+                    .text_size(px(9.0 * zoom))
+                    .text_color(text_color.opacity(0.7))
+                    .child(field.name.clone())
+            }))
+    }
 
-        // Different background colors for partition vs swc
+    /// Render a child element and its descendants at their layout positions
+    ///
+    /// `selected` is the path of the selected child relative to this one; empty selects this child.
+    fn render_child(
+        child: &NodeChild,
+        layout: &ChildLayout,
+        selected: Option<&[usize]>,
+        zoom: f32,
+        text_color: Hsla,
+        border_color: Hsla,
+        selected_border: Hsla,
+    ) -> Stateful<Div> {
+        let positioned = div()
+            .id(SharedString::from(format!("{}-{}", child.kind, child.name)))
+            .absolute()
+            .left(px(layout.x * zoom))
+            .top(px(layout.y * zoom))
+            .w(px(layout.width * zoom))
+            .h(px(layout.height * zoom));
+
+        if child.is_member() {
+            return positioned.child(Self::render_member(child, zoom, text_color));
+        }
+
+        // Background colors per container kind
         let (bg, label_color) = match child.kind.as_str() {
-            "partition" => (rgb(0x2a4a6a), rgb(0x88aacc)), // Blue-ish for partitions
-            "swc" => (rgb(0x3a5a3a), rgb(0x88cc88)),       // Green-ish for swc
+            "hypervisor" => (rgb(0x5a3a3a), rgb(0xcc8888)), // Red-ish for hypervisors
+            "vm" => (rgb(0x4a3a5a), rgb(0xbb99dd)),         // Violet for virtual machines
+            "partition" => (rgb(0x2a4a6a), rgb(0x88aacc)),  // Blue-ish for partitions
+            "composition" => (rgb(0x2a5a5a), rgb(0x88cccc)), // Teal for compositions
+            "swc" => (rgb(0x3a5a3a), rgb(0x88cc88)),        // Green-ish for swc
             _ => (rgb(0x4a4a4a), rgb(0xaaaaaa)),
         };
 
-        let mut container = div()
-            .m(px(2.0 * zoom))
-            .p(px(4.0 * zoom))
-            .min_w(px(child_width * zoom))
-            .bg(bg)
-            .border(px(1.0))
-            .border_color(border_color)
-            .rounded(px(3.0 * zoom))
+        let label = div()
+            .h(px(LABEL_HEIGHT * zoom))
             .flex()
             .flex_col()
-            .gap(px(2.0 * zoom))
+            .overflow_hidden()
             .child(
                 div()
                     .text_size(px(9.0 * zoom))
                     .text_color(label_color)
-                    .child(format!("«{}»", child.kind)),
+                    .child(if child.collapsed {
+                        format!("«{}» ▸ {}", child.kind, child.children.len())
+                    } else {
+                        format!("«{}»", child.kind)
+                    }),
             )
             .child(
                 div()
@@ -124,24 +536,61 @@ impl GraphNode {
                     .child(child.name.clone()),
             );
 
-        // Add nested children (swcs inside partitions)
-        if !child.children.is_empty() {
-            let children_container = div().flex().flex_wrap().gap(px(2.0 * zoom)).children(
+        positioned
+            .when_some(details_tooltip(&child.details), |this, tooltip| {
+                this.tooltip(tooltip)
+            })
+            // Dragging a child into another container moves it there in the model
+            .when_some(child.span, |this, span| {
+                this.on_drag(
+                    ChildDrag { span },
+                    |_drag: &ChildDrag, _offset, _window, cx| cx.new(|_| DragPreview),
+                )
+            })
+            .p(px(PADDING * zoom))
+            .bg(bg)
+            .border(px(1.0))
+            .border_color(if selected.is_some_and(|path| path.is_empty()) {
+                selected_border
+            } else {
+                border_color
+            })
+            .rounded(px(3.0 * zoom))
+            .child(label)
+            .when(!child.ports.is_empty(), |this| {
+                this.child(Self::render_ports(
+                    &child.ports,
+                    zoom,
+                    text_color,
+                    border_color,
+                ))
+            })
+            // Nested children are positioned relative to this box
+            .children(
                 child
-                    .children
+                    .visible_children()
                     .iter()
-                    .map(|c| Self::render_child(c, zoom, text_color, border_color)),
-            );
-            container = container.child(children_container);
-        }
-
-        container
+                    .zip(&layout.children)
+                    .enumerate()
+                    .map(|(index, (c, l))| {
+                        let selected = Self::selected_within(selected, index);
+                        Self::render_child(
+                            c,
+                            l,
+                            selected,
+                            zoom,
+                            text_color,
+                            border_color,
+                            selected_border,
+                        )
+                    }),
+            )
     }
 }
 
 impl Render for GraphNode {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
-        let header_height = 28.0f32;
+        let header_height = HEADER_HEIGHT;
         let port_size = 10.0f32;
 
         // Get theme colors
@@ -154,12 +603,12 @@ impl Render for GraphNode {
         let (type_bg, type_label_color) = match self.node_type.as_str() {
             "ecu" => (rgb(0x4a3a6a), rgb(0xcc88ff)), // Purple for ECU
             "bus" => (rgb(0x6a5a3a), rgb(0xffcc88)), // Orange for bus
+            "interface" => (rgb(0x2a4a6a), rgb(0x88aacc)), // Blue for interface
             _ => (rgb(0x4a4a4a), rgb(0xaaaaaa)),
         };
 
         // Calculate node dimensions - use estimate_dimensions for consistency
         let (node_width, node_height) = self.estimate_dimensions();
-        let has_children = !self.children.is_empty();
 
         // Update stored dimensions for edge routing and hit testing
         self.width = node_width;
@@ -173,6 +622,35 @@ impl Render for GraphNode {
             .flex()
             .items_center()
             .gap(px(8.0 * self.zoom))
+            .when(!self.children.is_empty(), |this| {
+                // Collapse toggle, handled here so it neither selects nor drags the node
+                this.child(
+                    div()
+                        .text_size(px(9.0 * self.zoom))
+                        .text_color(text_color)
+                        .cursor_pointer()
+                        .child(if self.collapsed { "▸" } else { "▾" })
+                        .on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(|this, _e: &MouseDownEvent, _window, cx| {
+                                cx.stop_propagation();
+                                let Some(graph_entity) = this.graph_entity.clone() else {
+                                    this.toggle_collapsed(cx);
+                                    return;
+                                };
+                                // The graph records the arrangement for undo first, which
+                                // reads this node, so it runs after this update
+                                let node = cx.entity();
+                                cx.defer(move |cx| {
+                                    cx.update_entity(&graph_entity, |graph, cx| {
+                                        graph.record_layout(cx);
+                                        graph.toggle_collapsed(&node, &[], cx);
+                                    });
+                                });
+                            }),
+                        ),
+                )
+            })
             .child(
                 // Type badge
                 div()
@@ -187,34 +665,48 @@ impl Render for GraphNode {
             .child(
                 // Name
                 div()
+                    .id("name")
+                    .when_some(details_tooltip(&self.details), |this, tooltip| {
+                        this.tooltip(tooltip)
+                    })
                     .text_size(px(11.0 * self.zoom))
                     .text_color(text_color)
                     .font_weight(FontWeight::MEDIUM)
                     .child(self.name.clone()),
-            );
-
-        // Children container (partitions and swcs) - stack vertically
-        let children_container = if has_children {
-            Some(
-                div()
-                    .w_full()
-                    .px(px(4.0 * self.zoom))
-                    .pb(px(4.0 * self.zoom))
-                    .flex()
-                    .flex_col() // Stack partitions vertically
-                    .gap(px(4.0 * self.zoom))
-                    .children(
-                        self.children
-                            .iter()
-                            .map(|c| Self::render_child(c, self.zoom, text_color, border_color)),
-                    ),
             )
-        } else {
-            None
-        };
+            .when(self.pinned, |this| {
+                this.child(
+                    div()
+                        .text_size(px(9.0 * self.zoom))
+                        .text_color(text_color)
+                        .child("📌"),
+                )
+            });
+
+        // Children are placed at their computed layout positions inside the node body
+        let child_layouts = self.child_layouts();
+        let children = self
+            .visible_children()
+            .iter()
+            .zip(&child_layouts)
+            .enumerate()
+            .map(|(index, (c, l))| {
+                let selected = self.selected_child.as_deref().filter(|_| self.selected);
+                Self::render_child(
+                    c,
+                    l,
+                    Self::selected_within(selected, index),
+                    self.zoom,
+                    text_color,
+                    border_color,
+                    selected_border,
+                )
+            })
+            .collect::<Vec<_>>();
 
         // Left port (incoming)
         let left_port = div()
+            .id("port-in")
             .absolute()
             .left(px(-port_size / 2.0 * self.zoom))
             .top(px((header_height - port_size) / 2.0 * self.zoom))
@@ -226,6 +718,7 @@ impl Render for GraphNode {
 
         // Right port (outgoing)
         let right_port = div()
+            .id("port-out")
             .absolute()
             .right(px(-port_size / 2.0 * self.zoom))
             .top(px((header_height - port_size) / 2.0 * self.zoom))
@@ -235,11 +728,29 @@ impl Render for GraphNode {
             .border_color(border_color)
             .rounded(px(2.0 * self.zoom));
 
+        // Dragging from a port onto another node connects the two in the model
+        let (left_port, right_port) = match self.span {
+            Some(span) => (
+                left_port.cursor_crosshair().on_drag(
+                    PortDrag { span },
+                    |_drag: &PortDrag, _offset, _window, cx| cx.new(|_| DragPreview),
+                ),
+                right_port.cursor_crosshair().on_drag(
+                    PortDrag { span },
+                    |_drag: &PortDrag, _offset, _window, cx| cx.new(|_| DragPreview),
+                ),
+            ),
+            None => (left_port, right_port),
+        };
+
         // Node body - use fixed width for consistent edge routing
-        let mut node_body = div()
+        let node_body = div()
             .id(("node", self.id as usize))
+            .relative()
             .w(px(node_width * self.zoom))
+            .h(px(node_height * self.zoom))
             .bg(bg_color)
+            .when(self.bar_width.is_some(), |this| this.bg(type_bg))
             .border(px(2.0))
             .border_color(if self.selected {
                 selected_border
@@ -252,6 +763,7 @@ impl Render for GraphNode {
             .flex_col()
             .cursor_move()
             .child(header)
+            .children(children)
             // Start a drag with this node's id as payload
             .on_drag(self.id, |_id: &u64, _offset, _window, cx| {
                 cx.new(|_| DragPreview)
@@ -282,12 +794,18 @@ impl Render for GraphNode {
                                 / this.zoom
                                 - offset.y,
                         );
+                        let delta = point(new_origin.x - this.x, new_origin.y - this.y);
                         this.x = new_origin.x;
                         this.y = new_origin.y;
-                        // Notify graph that this node moved
+                        // Notify graph that this node moved, the rest of the selection follows
                         if let Some(graph_entity) = &this.graph_entity {
+                            let dragged = _cx.entity_id();
+                            let selected = this.selected;
                             _cx.update_entity(graph_entity, |graph, cx| {
-                                graph.handle_node_moved(this.id, cx);
+                                if selected {
+                                    graph.move_selected(dragged, delta, cx);
+                                }
+                                graph.handle_node_moved(dragged, selected, cx);
                             });
                         }
                     }
@@ -296,6 +814,8 @@ impl Render for GraphNode {
             .on_drop(cx.listener(|this, dragged_id: &u64, _window, _cx| {
                 if *dragged_id == this.id {
                     this.drag_offset = None;
+                    // A node placed by hand stays where it was dropped
+                    this.pinned = true;
                     // Notify graph that dragging ended (defer to avoid entity conflicts)
                     if let Some(graph_entity) = &this.graph_entity {
                         let graph_entity = graph_entity.clone();
@@ -306,12 +826,35 @@ impl Render for GraphNode {
                         });
                     }
                 }
+            }))
+            .on_drop(cx.listener(|this, drag: &PortDrag, _window, cx| {
+                if let Some(span) = this.span.filter(|span| *span != drag.span)
+                    && let Some(graph_entity) = &this.graph_entity
+                {
+                    cx.update_entity(graph_entity, |_, cx| {
+                        cx.emit(ModelEdit::Connect {
+                            source: drag.span,
+                            target: span,
+                        });
+                    });
+                }
+            }))
+            .on_drop(cx.listener(|this, drag: &ChildDrag, window, cx| {
+                let position = window.mouse_position();
+                let x = (position.x - this.container_offset.x - this.pan.x) / this.zoom - this.x;
+                let y = (position.y - this.container_offset.y - this.pan.y) / this.zoom - this.y;
+                if let Some(parent) = this.drop_target(drag.span, x / px(1.0), y / px(1.0))
+                    && let Some(graph_entity) = &this.graph_entity
+                {
+                    cx.update_entity(graph_entity, |_, cx| {
+                        cx.emit(ModelEdit::Reparent {
+                            span: drag.span,
+                            parent,
+                        });
+                    });
+                }
             }));
 
-        if let Some(children) = children_container {
-            node_body = node_body.child(children);
-        }
-
         // Wrapper to position ports relative to node_body
         let node_wrapper = div()
             .relative()
@@ -328,6 +871,32 @@ impl Render for GraphNode {
     }
 }
 
+/// Tooltip listing model attributes as `name: value` lines, none without attributes
+fn details_tooltip(
+    details: &[(String, String)],
+) -> Option<impl Fn(&mut Window, &mut App) -> AnyView + 'static> {
+    if details.is_empty() {
+        return None;
+    }
+    let text: SharedString = details
+        .iter()
+        .map(|(name, value)| format!("{}: {}", name, value))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .into();
+    Some(move |window: &mut Window, cx: &mut App| Tooltip::new(text.clone()).build(window, cx))
+}
+
+/// Drag payload of a port, for connecting the node with the one it is dropped on
+struct PortDrag {
+    span: (usize, usize),
+}
+
+/// Drag payload of a nested child, for moving it into the container it is dropped on
+struct ChildDrag {
+    span: (usize, usize),
+}
+
 // Minimal drag preview view to satisfy on_drag constructor
 struct DragPreview;
 impl Render for DragPreview {
@@ -336,3 +905,74 @@ impl Render for DragPreview {
         div().size(px(1.0)).bg(rgb(0xffffff)).opacity(0.0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn child(kind: &str, name: &str, children: Vec<NodeChild>) -> NodeChild {
+        NodeChild {
+            name: name.to_string(),
+            kind: kind.to_string(),
+            children,
+            ports: Vec::new(),
+            span: None,
+            collapsed: false,
+            details: Vec::new(),
+        }
+    }
+
+    fn node(children: Vec<NodeChild>) -> GraphNode {
+        GraphNode {
+            id: 1,
+            key: "EngineECU".to_string(),
+            name: "EngineECU".to_string(),
+            node_type: "ecu".to_string(),
+            x: px(0.0),
+            y: px(0.0),
+            drag_offset: None,
+            zoom: 1.0,
+            pan: point(px(0.0), px(0.0)),
+            selected: false,
+            selected_child: None,
+            collapsed: false,
+            pinned: false,
+            hint: None,
+            container_offset: point(px(0.0), px(0.0)),
+            width: 0.0,
+            height: 0.0,
+            children,
+            bar_width: None,
+            span: None,
+            details: Vec::new(),
+            graph_entity: None,
+        }
+    }
+
+    #[test]
+    fn test_visible_path() {
+        let partition = |name| {
+            child(
+                "partition",
+                name,
+                vec![child("swc", "A", Vec::new()), child("swc", "B", Vec::new())],
+            )
+        };
+        let mut node = node(vec![child(
+            "vm",
+            "Linux",
+            vec![partition("P1"), partition("P2")],
+        )]);
+        assert_eq!(node.visible_path(None), None);
+        assert_eq!(node.visible_path(Some(&[0, 1, 0])), Some(vec![0, 1, 0]));
+
+        // Edges into a collapsed partition attach to the partition, not to the node
+        node.children[0].children[0].collapsed = true;
+        assert_eq!(node.visible_path(Some(&[0, 0, 1])), Some(vec![0, 0]));
+        assert_eq!(node.visible_path(Some(&[0, 1, 1])), Some(vec![0, 1, 1]));
+
+        // A collapsed node shows no children at all
+        node.collapsed = true;
+        assert_eq!(node.visible_path(Some(&[0, 0, 1])), None);
+    }
+}
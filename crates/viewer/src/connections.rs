@@ -0,0 +1,290 @@
+//! Connections between software components derived from matching port declarations
+
+use crate::model::{ArchModel, Ecu, Port, PortDirection, Span};
+
+/// A port identified by its location in the model
+#[derive(Clone, Debug)]
+pub struct PortRef<'a> {
+    pub ecu: &'a Ecu,
+    pub swc: &'a str,
+    pub port: &'a Port,
+}
+
+impl PortRef<'_> {
+    /// Qualified name used in messages, e.g. `EngineECU.TorqueCoordinator.EngineDataService`
+    pub fn path(&self) -> String {
+        format!("{}.{}.{}", self.ecu.name, self.swc, self.port.name)
+    }
+
+    fn same_swc(&self, other: &PortRef) -> bool {
+        self.ecu.name == other.ecu.name && self.swc == other.swc
+    }
+}
+
+/// Data flow from an `output_port` to an `input_port` carrying the same signal
+#[derive(Clone, Debug)]
+pub struct SignalConnection<'a> {
+    pub signal: &'a str,
+    pub source: PortRef<'a>,
+    pub target: PortRef<'a>,
+    /// Bus shared by both ECUs; `None` if both ports live on the same ECU or no bus is shared
+    pub bus: Option<&'a str>,
+}
+
+impl SignalConnection<'_> {
+    pub fn is_local(&self) -> bool {
+        self.source.ecu.name == self.target.ecu.name
+    }
+
+    /// Span of the receiving `signal=` property, where problems with the connection are reported
+    pub fn target_span(&self) -> Span {
+        self.target
+            .port
+            .signal
+            .as_ref()
+            .map(|s| s.span)
+            .unwrap_or(self.target.port.span)
+    }
+}
+
+/// A `required_port` bound to the `provided_port` offering its service
+#[derive(Clone, Debug)]
+pub struct ServiceConnection<'a> {
+    pub interface: &'a str,
+    pub provider: PortRef<'a>,
+    pub consumer: PortRef<'a>,
+    /// Bus shared by both ECUs; `None` if both ports live on the same ECU or no bus is shared
+    pub bus: Option<&'a str>,
+}
+
+/// Outcome of looking up the provider of a `required_port`
+#[derive(Clone, Debug)]
+pub enum ServiceBinding<'a> {
+    /// Provider found with matching interface and service_id
+    Connected(ServiceConnection<'a>),
+    /// The interface is provided, but under a different service_id
+    ServiceIdMismatch {
+        consumer: PortRef<'a>,
+        provider: PortRef<'a>,
+    },
+    /// No port provides the interface (or the required port names none)
+    Unresolved { consumer: PortRef<'a> },
+}
+
+/// All ports of the given direction in the model
+pub fn ports(model: &ArchModel, direction: PortDirection) -> impl Iterator<Item = PortRef<'_>> {
+    model.swcs().flat_map(move |(ecu, swc)| {
+        swc.ports
+            .iter()
+            .filter(move |port| port.direction == direction)
+            .map(move |port| PortRef {
+                ecu,
+                swc: &swc.name,
+                port,
+            })
+    })
+}
+
+/// Match every output signal to the input ports of other software components using it
+pub fn signal_connections(model: &ArchModel) -> Vec<SignalConnection<'_>> {
+    let mut connections = Vec::new();
+    for source in ports(model, PortDirection::Output) {
+        let Some(signal) = source.port.signal.as_ref() else {
+            continue;
+        };
+        for target in ports(model, PortDirection::Input) {
+            let target_signal = target.port.signal.as_ref().map(|s| s.name.as_str());
+            if target_signal != Some(signal.name.as_str()) || source.same_swc(&target) {
+                continue;
+            }
+            connections.push(SignalConnection {
+                signal: &signal.name,
+                bus: route_bus(source.ecu, target.ecu),
+                source: source.clone(),
+                target,
+            });
+        }
+    }
+    connections
+}
+
+/// Resolve each `required_port` to its provider by interface name and service_id
+pub fn service_bindings(model: &ArchModel) -> Vec<ServiceBinding<'_>> {
+    let providers: Vec<PortRef> = ports(model, PortDirection::Provided).collect();
+
+    ports(model, PortDirection::Required)
+        .map(|consumer| {
+            let Some(interface) = consumer.port.interface.as_deref() else {
+                return ServiceBinding::Unresolved { consumer };
+            };
+            let candidates: Vec<&PortRef> = providers
+                .iter()
+                .filter(|p| p.port.interface.as_deref() == Some(interface))
+                .collect();
+            // Without a service_id any provider of the interface will do
+            let matching = candidates.iter().find(|p| {
+                consumer.port.service_id.is_none() || p.port.service_id == consumer.port.service_id
+            });
+            match (matching, candidates.first()) {
+                (Some(provider), _) => ServiceBinding::Connected(ServiceConnection {
+                    interface,
+                    bus: route_bus(provider.ecu, consumer.ecu),
+                    provider: (*provider).clone(),
+                    consumer,
+                }),
+                (None, Some(provider)) => ServiceBinding::ServiceIdMismatch {
+                    consumer,
+                    provider: (*provider).clone(),
+                },
+                (None, None) => ServiceBinding::Unresolved { consumer },
+            }
+        })
+        .collect()
+}
+
+/// Successfully bound services only
+pub fn service_connections(model: &ArchModel) -> Vec<ServiceConnection<'_>> {
+    service_bindings(model)
+        .into_iter()
+        .filter_map(|binding| match binding {
+            ServiceBinding::Connected(connection) => Some(connection),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Bus to route a connection between two ECUs over (`None` on the same ECU)
+fn route_bus<'a>(a: &'a Ecu, b: &Ecu) -> Option<&'a str> {
+    if a.name == b.name {
+        return None;
+    }
+    // First bus both ECUs are attached to, in the interface order of `a`
+    a.interfaces
+        .iter()
+        .filter_map(|i| i.bus.as_deref())
+        .find(|bus| {
+            b.interfaces
+                .iter()
+                .any(|other| other.bus.as_deref() == Some(*bus))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kdl::parse_model;
+
+    const MODEL: &str = r#"
+        CAN type="bus"
+        ETH type="bus"
+        EngineECU type="ecu" {
+            interface "can0" bus="CAN"
+            interface "eth0" bus="ETH"
+            swc "Engine" {
+                output_port "RPM" signal="RPM"
+                provided_port "EngineData" {
+                    interface "EngineService"
+                    service_id 1
+                }
+            }
+            swc "Monitor" {
+                input_port "RPM" signal="RPM"
+            }
+        }
+        BodyECU type="ecu" {
+            interface "eth0" bus="ETH"
+            swc "Dashboard" {
+                input_port "RPM" signal="RPM"
+                required_port "Engine" {
+                    interface "EngineService"
+                    service_id 1
+                }
+                required_port "Wrong" {
+                    interface "EngineService"
+                    service_id 2
+                }
+                required_port "Climate" {
+                    interface "ClimateService"
+                }
+                required_port "Nothing"
+            }
+        }
+        DoorECU type="ecu" {
+            interface "can0" bus="CAN"
+        }
+    "#;
+
+    fn model() -> ArchModel {
+        parse_model(MODEL).model.unwrap()
+    }
+
+    #[test]
+    fn test_signal_connections() {
+        let model = model();
+        let connections: Vec<_> = signal_connections(&model)
+            .iter()
+            .map(|c| (c.source.path(), c.target.path(), c.bus, c.is_local()))
+            .collect();
+        assert_eq!(
+            connections,
+            [
+                (
+                    "EngineECU.Engine.RPM".to_string(),
+                    "EngineECU.Monitor.RPM".to_string(),
+                    None,
+                    true
+                ),
+                (
+                    "EngineECU.Engine.RPM".to_string(),
+                    "BodyECU.Dashboard.RPM".to_string(),
+                    Some("ETH"),
+                    false
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_service_bindings() {
+        let model = model();
+        let bindings = service_bindings(&model);
+        assert_eq!(bindings.len(), 4);
+
+        let ServiceBinding::Connected(connection) = &bindings[0] else {
+            panic!("expected a connection, got {:?}", bindings[0]);
+        };
+        assert_eq!(connection.interface, "EngineService");
+        assert_eq!(connection.provider.path(), "EngineECU.Engine.EngineData");
+        assert_eq!(connection.consumer.path(), "BodyECU.Dashboard.Engine");
+        assert_eq!(connection.bus, Some("ETH"));
+
+        let ServiceBinding::ServiceIdMismatch { consumer, provider } = &bindings[1] else {
+            panic!("expected a service_id mismatch, got {:?}", bindings[1]);
+        };
+        assert_eq!(consumer.path(), "BodyECU.Dashboard.Wrong");
+        assert_eq!(provider.path(), "EngineECU.Engine.EngineData");
+
+        // Neither an unknown interface nor none at all can be bound
+        for (binding, path) in bindings[2..]
+            .iter()
+            .zip(["BodyECU.Dashboard.Climate", "BodyECU.Dashboard.Nothing"])
+        {
+            let ServiceBinding::Unresolved { consumer } = binding else {
+                panic!("expected no binding, got {:?}", binding);
+            };
+            assert_eq!(consumer.path(), path);
+        }
+
+        assert_eq!(service_connections(&model).len(), 1);
+    }
+
+    #[test]
+    fn test_route_bus() {
+        let model = model();
+        let ecu = |name| model.ecu(name).unwrap();
+        assert_eq!(route_bus(ecu("EngineECU"), ecu("BodyECU")), Some("ETH"));
+        assert_eq!(route_bus(ecu("EngineECU"), ecu("DoorECU")), Some("CAN"));
+        assert_eq!(route_bus(ecu("BodyECU"), ecu("DoorECU")), None);
+        assert_eq!(route_bus(ecu("EngineECU"), ecu("EngineECU")), None);
+    }
+}
@@ -0,0 +1,68 @@
+//! Diagnostics reported while parsing and validating a model document
+
+use crate::model::Span;
+
+/// How severe a diagnostic is
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A problem found in the model document, located by its byte span
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub span: Span,
+    /// Optional suggestion how to fix the problem
+    pub hint: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn error(span: Span, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            message: message.into(),
+            span,
+            hint: None,
+        }
+    }
+
+    pub fn warning(span: Span, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::error(span, message)
+        }
+    }
+
+    pub fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    /// Convert KDL syntax errors of a document starting at offset `base` into diagnostics
+    pub fn from_kdl_error(error: &kdl::KdlError, base: usize) -> Vec<Self> {
+        error
+            .diagnostics
+            .iter()
+            .map(|diag| {
+                let start = base + diag.span.offset();
+                let message = diag
+                    .message
+                    .clone()
+                    .or_else(|| diag.label.clone())
+                    .unwrap_or_else(|| "Syntax error".to_string());
+                let diagnostic = Self::error(Span::new(start, start + diag.span.len()), message);
+                match &diag.help {
+                    Some(help) => diagnostic.with_hint(help.clone()),
+                    None => diagnostic,
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,391 @@
+//! Text edits for changes made in the graph
+//!
+//! Each change touches as little of the document as possible: a rename replaces the name,
+//! a delete removes the element's lines and new elements are inserted as new lines. The
+//! rest of the document, comments and formatting included, stays as it is.
+
+use crate::model::Span;
+use std::path::PathBuf;
+
+/// Indentation of one nesting level in inserted text
+const INDENT: &str = "    ";
+
+/// Replacement of a byte range in one document
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextEdit {
+    pub span: Span,
+    pub text: String,
+}
+
+impl TextEdit {
+    fn insert(offset: usize, text: String) -> Self {
+        Self {
+            span: Span::new(offset, offset),
+            text,
+        }
+    }
+}
+
+/// Apply non-overlapping edits to a text
+pub fn apply(text: &str, mut edits: Vec<TextEdit>) -> String {
+    // Back to front, so earlier offsets stay valid
+    edits.sort_by_key(|edit| std::cmp::Reverse(edit.span.start));
+    let mut text = text.to_string();
+    for edit in edits {
+        text.replace_range(edit.span.start..edit.span.end, &edit.text);
+    }
+    text
+}
+
+/// Before and after an edit of one file, `None` being the built-in example
+#[derive(Clone, Debug)]
+pub struct FileChange {
+    pub path: Option<PathBuf>,
+    pub before: String,
+    pub after: String,
+}
+
+/// The single edit turning `old` into `new`, replacing what lies between their common
+/// start and end
+pub fn diff(old: &str, new: &str) -> Option<TextEdit> {
+    if old == new {
+        return None;
+    }
+    let mut prefix = old
+        .bytes()
+        .zip(new.bytes())
+        .take_while(|(a, b)| a == b)
+        .count();
+    while !old.is_char_boundary(prefix) || !new.is_char_boundary(prefix) {
+        prefix -= 1;
+    }
+    let max_suffix = old.len().min(new.len()) - prefix;
+    let mut suffix = old
+        .bytes()
+        .rev()
+        .zip(new.bytes().rev())
+        .take(max_suffix)
+        .take_while(|(a, b)| a == b)
+        .count();
+    while !old.is_char_boundary(old.len() - suffix) || !new.is_char_boundary(new.len() - suffix) {
+        suffix -= 1;
+    }
+    Some(TextEdit {
+        span: Span::new(prefix, old.len() - suffix),
+        text: new[prefix..new.len() - suffix].to_string(),
+    })
+}
+
+/// Rename the element starting at `start`: top-level elements are named by the node name,
+/// nested ones by their first argument (`swc "Name"`)
+pub fn rename(text: &str, start: usize, nested: bool, name: &str) -> Option<TextEdit> {
+    let mut token = token_at(text, start)?;
+    if nested {
+        let argument = text.len() - text[token.end..].trim_start().len();
+        token = token_at(text, argument)?;
+        // A property is not a name
+        if text[token.end..].starts_with('=') {
+            return None;
+        }
+    }
+    let name = if nested || !is_identifier(name) {
+        quoted(name)
+    } else {
+        name.to_string()
+    };
+    Some(TextEdit {
+        span: token,
+        text: name,
+    })
+}
+
+/// Remove the element at `span`, with its whole line unless something else is on it
+pub fn delete(text: &str, span: Span) -> TextEdit {
+    let end = trimmed_end(text, span);
+    let line_start = line_start(text, span.start);
+    let line_end = text[end..].find('\n').map_or(text.len(), |i| end + i + 1);
+    let before = &text[line_start..span.start];
+    let after = text[end..line_end].trim_start_matches(|c: char| c == ';' || c.is_whitespace());
+    let span = if before.trim().is_empty() && (after.is_empty() || after.starts_with("//")) {
+        Span::new(line_start, line_end)
+    } else {
+        Span::new(span.start, end)
+    };
+    TextEdit {
+        span,
+        text: String::new(),
+    }
+}
+
+/// Text of the element at `span`, with the indentation of its first line removed from all
+/// lines, to be inserted elsewhere
+pub fn element_text(text: &str, span: Span) -> String {
+    let indent = &text[line_start(text, span.start)..span.start];
+    let indent = if indent.trim().is_empty() { indent } else { "" };
+    text[span.start..trimmed_end(text, span)]
+        .lines()
+        .map(|line| line.strip_prefix(indent).unwrap_or(line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Insert `element` as the last child of the element at `parent`, adding a children block
+/// if it has none
+pub fn insert_child(text: &str, parent: Span, element: &str) -> TextEdit {
+    let indent = indent_at(text, parent.start);
+    let child_indent = format!("{}{}", indent, INDENT);
+    let element = indented(element, &child_indent);
+    let end = trimmed_end(text, parent);
+    if !text[parent.start..end].ends_with('}') {
+        return TextEdit::insert(end, format!(" {{\n{}\n{}}}", element, indent));
+    }
+    let brace = end - 1;
+    let brace_line = line_start(text, brace);
+    if text[brace_line..brace].trim().is_empty() {
+        // Closing brace on a line of its own: the new child goes on the line before it
+        TextEdit::insert(brace_line, format!("{}\n", element))
+    } else {
+        TextEdit::insert(brace, format!("\n{}\n{}", element, indent))
+    }
+}
+
+/// Insert `element` on a new line after the element at `sibling`, at the same depth
+pub fn insert_after(text: &str, sibling: Span, element: &str) -> TextEdit {
+    let indent = indent_at(text, sibling.start);
+    let end = trimmed_end(text, sibling);
+    TextEdit::insert(end, format!("\n{}", indented(element, &indent)))
+}
+
+/// Append a top-level element to the end of the document, after a blank line
+pub fn append(text: &str, element: &str) -> TextEdit {
+    let separator = if text.trim().is_empty() || text.ends_with("\n\n") {
+        ""
+    } else if text.ends_with('\n') {
+        "\n"
+    } else {
+        "\n\n"
+    };
+    TextEdit::insert(text.len(), format!("{}{}\n", separator, element))
+}
+
+/// A string value in KDL syntax
+pub fn quoted(value: &str) -> String {
+    let mut quoted = String::from('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Whether a name can be written as a bare KDL identifier
+fn is_identifier(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+/// Byte range of the identifier or string starting at `start`
+fn token_at(text: &str, start: usize) -> Option<Span> {
+    let rest = text.get(start..)?;
+    let len = if let Some(string) = rest.strip_prefix('"') {
+        let mut escaped = false;
+        let close = string.char_indices().find(|&(_, c)| {
+            let close = c == '"' && !escaped;
+            escaped = c == '\\' && !escaped;
+            close
+        })?;
+        close.0 + 2
+    } else {
+        rest.find(|c: char| c.is_whitespace() || "{}();=\"".contains(c))
+            .unwrap_or(rest.len())
+    };
+    (len > 0).then(|| Span::new(start, start + len))
+}
+
+/// End of `span` without trailing whitespace
+fn trimmed_end(text: &str, span: Span) -> usize {
+    span.start + text[span.start..span.end].trim_end().len()
+}
+
+fn line_start(text: &str, offset: usize) -> usize {
+    text[..offset].rfind('\n').map_or(0, |i| i + 1)
+}
+
+/// Leading whitespace of the line containing `offset`
+fn indent_at(text: &str, offset: usize) -> String {
+    let line = &text[line_start(text, offset)..];
+    line[..line.len() - line.trim_start().len()].to_string()
+}
+
+/// Prefix every non-empty line with `indent`
+fn indented(text: &str, indent: &str) -> String {
+    text.lines()
+        .map(|line| {
+            if line.trim().is_empty() {
+                String::new()
+            } else {
+                format!("{}{}", indent, line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Span of the first occurrence of `element` in `text`
+    fn span_of(text: &str, element: &str) -> Span {
+        let start = text.find(element).unwrap();
+        Span::new(start, start + element.len())
+    }
+
+    #[test]
+    fn test_rename() {
+        let text = "EngineECU type=\"ecu\" {\n    swc \"Injection\"\n}\n";
+        let edit = rename(text, 0, false, "MotorECU").unwrap();
+        assert_eq!(
+            apply(text, vec![edit]),
+            "MotorECU type=\"ecu\" {\n    swc \"Injection\"\n}\n"
+        );
+
+        // Names that are no identifier get quoted
+        let edit = rename(text, 0, false, "Engine ECU").unwrap();
+        assert_eq!(
+            apply(text, vec![edit]),
+            "\"Engine ECU\" type=\"ecu\" {\n    swc \"Injection\"\n}\n"
+        );
+
+        let start = text.find("swc").unwrap();
+        let edit = rename(text, start, true, "Ignition").unwrap();
+        assert_eq!(
+            apply(text, vec![edit]),
+            "EngineECU type=\"ecu\" {\n    swc \"Ignition\"\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_rename_without_name() {
+        let text = "swc type=\"x\"\n";
+        assert_eq!(rename(text, 0, true, "Name"), None);
+    }
+
+    #[test]
+    fn test_delete() {
+        let text = "ecu {\n    swc \"A\"\n    swc \"B\" // brakes\n}\n";
+        let edit = delete(text, span_of(text, "swc \"A\"\n"));
+        assert_eq!(
+            apply(text, vec![edit]),
+            "ecu {\n    swc \"B\" // brakes\n}\n"
+        );
+
+        // A trailing comment goes with the line
+        let edit = delete(text, span_of(text, "swc \"B\""));
+        assert_eq!(apply(text, vec![edit]), "ecu {\n    swc \"A\"\n}\n");
+
+        // Other elements on the same line stay
+        let text = "ecu { swc \"A\"; swc \"B\"; }\n";
+        let edit = delete(text, span_of(text, "swc \"A\";"));
+        assert_eq!(apply(text, vec![edit]), "ecu {  swc \"B\"; }\n");
+    }
+
+    #[test]
+    fn test_insert_child() {
+        let text = "ecu {\n    swc \"A\"\n}\n";
+        let edit = insert_child(text, span_of(text, text.trim_end()), "swc \"B\"");
+        assert_eq!(
+            apply(text, vec![edit]),
+            "ecu {\n    swc \"A\"\n    swc \"B\"\n}\n"
+        );
+
+        // Without children, a block is added
+        let text = "ecu\n";
+        let edit = insert_child(text, span_of(text, "ecu"), "swc \"A\"");
+        assert_eq!(apply(text, vec![edit]), "ecu {\n    swc \"A\"\n}\n");
+
+        let text = "ecu { swc \"A\"; }\n";
+        let edit = insert_child(text, span_of(text, text.trim_end()), "swc \"B\"");
+        assert_eq!(
+            apply(text, vec![edit]),
+            "ecu { swc \"A\"; \n    swc \"B\"\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_insert_after_and_append() {
+        let text = "ecu {\n    interface \"can0\" bus=\"CAN\"\n}\n";
+        let sibling = span_of(text, "interface \"can0\" bus=\"CAN\"");
+        let edit = insert_after(text, sibling, "interface \"can1\" bus=\"CAN\"");
+        assert_eq!(
+            apply(text, vec![edit]),
+            "ecu {\n    interface \"can0\" bus=\"CAN\"\n    interface \"can1\" bus=\"CAN\"\n}\n"
+        );
+
+        assert_eq!(
+            apply("", vec![append("", "CAN type=\"bus\"")]),
+            "CAN type=\"bus\"\n"
+        );
+        let text = "ECU type=\"ecu\"\n";
+        assert_eq!(
+            apply(text, vec![append(text, "CAN type=\"bus\"")]),
+            "ECU type=\"ecu\"\n\nCAN type=\"bus\"\n"
+        );
+    }
+
+    #[test]
+    fn test_element_text() {
+        let text = "ecu {\n    swc \"A\" {\n        runnable \"Main\"\n    }\n}\n";
+        let span = span_of(text, "swc \"A\" {\n        runnable \"Main\"\n    }\n");
+        assert_eq!(
+            element_text(text, span),
+            "swc \"A\" {\n    runnable \"Main\"\n}"
+        );
+    }
+
+    #[test]
+    fn test_diff() {
+        assert_eq!(diff("same", "same"), None);
+
+        let edit = diff("swc \"A\"\n", "swc \"Bee\"\n").unwrap();
+        assert_eq!(edit.span, Span::new(5, 6));
+        assert_eq!(edit.text, "Bee");
+
+        // Repeated characters are not counted twice
+        let edit = diff("aaa", "aa").unwrap();
+        assert_eq!(edit.span, Span::new(2, 3));
+        assert_eq!(edit.text, "");
+
+        // Never splits a character
+        let edit = diff("a→b", "a←b").unwrap();
+        assert_eq!(edit.span, Span::new(1, 4));
+        assert_eq!(edit.text, "←");
+    }
+
+    #[test]
+    fn test_apply() {
+        let text = "0123456789";
+        let edits = vec![
+            TextEdit::insert(2, "ab".to_string()),
+            TextEdit {
+                span: Span::new(5, 8),
+                text: "x".to_string(),
+            },
+        ];
+        assert_eq!(apply(text, edits), "01ab234x89");
+    }
+
+    #[test]
+    fn test_quoted() {
+        assert_eq!(quoted("a \"b\"\\c\n"), "\"a \\\"b\\\"\\\\c\\n\"");
+    }
+}
@@ -1,260 +1,770 @@
-//! KDL model parsing utilities for extracting graph nodes and edges
-
-use gpui::{point, px};
-use graphview::{GraphEdge, GraphNode, NodeChild};
-use std::collections::HashMap;
-
-/// Parse KDL content and extract nodes (ECUs and buses) with their connections
-pub fn parse_kdl_model(content: &str) -> (Vec<GraphNode>, Vec<GraphEdge>) {
-    let mut nodes = Vec::new();
-    let mut edges = Vec::new();
-    let mut node_name_to_index: HashMap<String, usize> = HashMap::new();
-
-    // Parse the KDL document
-    let doc = match kdl::KdlDocument::parse(content) {
-        Ok(doc) => doc,
-        Err(_) => return (nodes, edges),
-    };
+//! KDL model parsing utilities for building the typed architecture model
+
+use crate::diagnostics::Diagnostic;
+use crate::model::{
+    ArchModel, Bus, Container, ContainmentSchema, Ecu, Element, Interface, Operation, Port,
+    PortDirection, Runnable, ServiceInterface, ServiceMember, Signal, Span, Swc, TypedField,
+};
+use crate::sources::Sources;
+use crate::validate::validate;
+use std::io;
+use std::path::{Path, PathBuf};
+use tracing::error;
+
+/// Outcome of parsing a document: the model (if the syntax is valid) and all diagnostics
+pub struct ParseResult {
+    pub model: Option<ArchModel>,
+    pub diagnostics: Vec<Diagnostic>,
+    /// The root document and all documents it includes
+    pub sources: Sources,
+    /// Number of leading diagnostics from reading the documents, before validation
+    pub load_diagnostics: usize,
+}
 
-    // First pass: collect all nodes (ECUs and buses)
-    let mut id: u64 = 1;
-    let mut index: usize = 0;
-
-    // Layout parameters for positioning nodes
-    let bus_y = 50.0f32;
-    let ecu_y = 150.0f32;
-    let start_x = 50.0f32;
-
-    // First pass: collect all nodes with their estimated sizes
-    struct NodeInfo {
-        name: String,
-        node_type: String,
-        children: Vec<NodeChild>,
-        estimated_width: f32,
-        estimated_height: f32,
-        span: Option<(usize, usize)>,
+impl ParseResult {
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(|d| d.is_error())
     }
 
-    let mut bus_nodes: Vec<NodeInfo> = Vec::new();
-    let mut ecu_nodes: Vec<NodeInfo> = Vec::new();
+    /// Errors reading the documents: syntax errors and broken includes. Validation errors
+    /// are not among them, the model is still built despite those.
+    pub fn load_errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics[..self.load_diagnostics]
+            .iter()
+            .filter(|d| d.is_error())
+    }
+}
 
-    for kdl_node in doc.nodes() {
-        let name = kdl_node.name().to_string();
+/// Parse KDL content into the typed architecture model and validate it.
+/// Included files are resolved relative to the working directory.
+pub fn parse_model(content: &str) -> ParseResult {
+    parse_project(None, content, |path| std::fs::read_to_string(path))
+}
 
-        // Check for type="ecu" or type="bus" in entries
-        let node_type = kdl_node
-            .entries()
-            .iter()
-            .find(|e| {
-                e.name()
-                    .map(|n| n.to_string().as_str() == "type")
-                    .unwrap_or(false)
-            })
-            .and_then(|e| e.value().as_string())
-            .map(|s| s.to_string());
-
-        if let Some(type_val) = node_type {
-            node_name_to_index.insert(name.clone(), index);
-            index += 1;
-
-            // Extract children (partitions and swcs) for ECUs
-            let children = if type_val == "ecu" {
-                extract_node_children(kdl_node)
-            } else {
-                Vec::new()
+/// Parse the root document stored at `path` together with everything it includes
+/// (`include "powertrain.kdl"`, relative to the including file). Included files are
+/// loaded with `read`, so callers can supply unsaved editor contents.
+pub fn parse_project(
+    path: Option<&Path>,
+    content: &str,
+    read: impl Fn(&Path) -> io::Result<String>,
+) -> ParseResult {
+    let mut loader = Loader::new(read);
+    let path = path.map(|p| p.canonicalize().unwrap_or_else(|_| p.to_path_buf()));
+    loader.load(path, content.to_string());
+    loader.finish()
+}
+
+/// Parse all documents of a project directory into one model. Documents that are also
+/// included by another one are loaded only once.
+pub fn parse_workspace(
+    paths: &[PathBuf],
+    read: impl Fn(&Path) -> io::Result<String>,
+) -> ParseResult {
+    let mut loader = Loader::new(read);
+    for path in paths {
+        if loader.sources.file(path).is_some() {
+            continue;
+        }
+        match (loader.read)(path) {
+            Ok(text) => loader.load(Some(path.clone()), text),
+            Err(error) => error!("Cannot read {}: {}", path.display(), error),
+        }
+    }
+    loader.finish()
+}
+
+/// Set `x=`/`y=` layout hints on the top-level nodes starting at the given byte offsets.
+/// The edit goes through the parsed document, so formatting and comments are kept.
+pub fn write_positions(
+    text: &str,
+    positions: &[(usize, (f32, f32))],
+) -> Result<String, kdl::KdlError> {
+    let mut doc = kdl::KdlDocument::parse(text)?;
+    for kdl_node in doc.nodes_mut() {
+        let offset = kdl_node.span().offset();
+        let Some((_, (x, y))) = positions.iter().find(|(start, _)| *start == offset) else {
+            continue;
+        };
+        for (key, value) in [("x", x), ("y", y)] {
+            let entry = kdl::KdlEntry::new_prop(key, kdl::KdlValue::Integer(value.round() as i128));
+            kdl_node.insert(key, entry);
+        }
+    }
+    Ok(doc.to_string())
+}
+
+/// Reads a document and, depth first, the documents it includes
+struct Loader<F> {
+    read: F,
+    sources: Sources,
+    /// Parsed documents in include order, spans moved into the combined offset space
+    documents: Vec<kdl::KdlDocument>,
+    diagnostics: Vec<Diagnostic>,
+    /// Files currently being loaded, to detect include cycles
+    stack: Vec<Option<PathBuf>>,
+    syntax_error: bool,
+}
+
+impl<F: Fn(&Path) -> io::Result<String>> Loader<F> {
+    fn new(read: F) -> Self {
+        Self {
+            read,
+            sources: Sources::default(),
+            documents: Vec::new(),
+            diagnostics: Vec::new(),
+            stack: Vec::new(),
+            syntax_error: false,
+        }
+    }
+
+    /// Build and validate the model from everything loaded
+    fn finish(self) -> ParseResult {
+        if self.syntax_error {
+            return ParseResult {
+                model: None,
+                load_diagnostics: self.diagnostics.len(),
+                diagnostics: self.diagnostics,
+                sources: self.sources,
             };
+        }
+        let model = build_model(&self.documents);
+        let load_diagnostics = self.diagnostics.len();
+        let mut diagnostics = self.diagnostics;
+        diagnostics.extend(validate(&model));
+        ParseResult {
+            model: Some(model),
+            diagnostics,
+            sources: self.sources,
+            load_diagnostics,
+        }
+    }
 
-            // Estimate node size for layout
-            let (estimated_width, estimated_height) =
-                GraphNode::estimate_node_size(&name, &type_val, &children);
-
-            // Try to get span/position from the KDL node (if available)
-            let s = kdl_node.span();
-            let start = s.offset();
-            let end = start + s.len();
-            let span = Some((start, end));
-
-            let info = NodeInfo {
-                name,
-                node_type: type_val.clone(),
-                children,
-                estimated_width,
-                estimated_height,
-                span,
+    fn load(&mut self, path: Option<PathBuf>, text: String) {
+        let base = self.sources.add(path.clone(), text.clone());
+        let mut doc = match kdl::KdlDocument::parse(&text) {
+            Ok(doc) => doc,
+            Err(error) => {
+                self.syntax_error = true;
+                self.diagnostics
+                    .extend(Diagnostic::from_kdl_error(&error, base));
+                return;
+            }
+        };
+        rebase(&mut doc, base);
+
+        let includes: Vec<(Option<String>, Span)> = doc
+            .nodes()
+            .iter()
+            .filter(|n| n.name().value() == "include")
+            .map(|n| {
+                let target = n.entries().iter().find(|e| e.name().is_none());
+                let target = target.and_then(|e| e.value().as_string());
+                (target.map(str::to_string), span_of(n))
+            })
+            .collect();
+        self.documents.push(doc);
+
+        let dir = path
+            .as_deref()
+            .and_then(Path::parent)
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        self.stack.push(path);
+        for (target, span) in includes {
+            let Some(target) = target else {
+                self.diagnostics.push(
+                    Diagnostic::error(span, "`include` needs the path of a file")
+                        .with_hint("Write it as `include \"powertrain.kdl\"`"),
+                );
+                continue;
             };
+            let joined = dir.join(&target);
+            let resolved = joined.canonicalize().unwrap_or(joined);
 
-            match type_val.as_str() {
-                "bus" => bus_nodes.push(info),
-                "ecu" => ecu_nodes.push(info),
-                _ => {}
+            if self.stack.contains(&Some(resolved.clone())) {
+                let chain: Vec<String> = self
+                    .stack
+                    .iter()
+                    .skip_while(|p| p.as_ref() != Some(&resolved))
+                    .map(|p| file_name(p.as_deref()))
+                    .chain([file_name(Some(&resolved))])
+                    .collect();
+                self.diagnostics.push(
+                    Diagnostic::error(span, format!("Include cycle: {}", chain.join(" → ")))
+                        .with_hint("Remove one of the includes"),
+                );
+                continue;
+            }
+            // Files included from several places are loaded once
+            if self.sources.file(&resolved).is_some() {
+                continue;
             }
+            match (self.read)(&resolved) {
+                Ok(text) => self.load(Some(resolved), text),
+                Err(error) => self.diagnostics.push(Diagnostic::error(
+                    span,
+                    format!("Cannot read included file `{}`: {}", target, error),
+                )),
+            }
+        }
+        self.stack.pop();
+    }
+}
+
+fn file_name(path: Option<&Path>) -> String {
+    path.and_then(Path::file_name)
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "untitled".to_string())
+}
+
+/// Move all spans of a parsed document by `base`, into the combined offset space of `Sources`
+fn rebase(doc: &mut kdl::KdlDocument, base: usize) {
+    for kdl_node in doc.nodes_mut() {
+        let s = kdl_node.span();
+        kdl_node.set_span((s.offset() + base, s.len()));
+        for entry in kdl_node.entries_mut() {
+            let s = entry.span();
+            entry.set_span((s.offset() + base, s.len()));
+        }
+        if let Some(children) = kdl_node.children_mut() {
+            rebase(children, base);
         }
     }
+}
 
-    // Layout buses in a row with proper spacing
-    let gap = 30.0f32;
-    let mut bus_x = start_x;
-    for info in &bus_nodes {
-        nodes.push(GraphNode {
-            id,
-            name: info.name.clone(),
-            node_type: info.node_type.clone(),
-            children: info.children.clone(),
-            x: px(bus_x),
-            y: px(bus_y),
-            drag_offset: None,
-            zoom: 1.0,
-            pan: point(px(0.0), px(0.0)),
-            selected: false,
-            container_offset: point(px(0.0), px(0.0)),
-            width: info.estimated_width,
-            height: info.estimated_height,
-            span: info.span,
-            graph_entity: None,
-        });
-        bus_x += info.estimated_width + gap;
-        id += 1;
+/// Build the model from all documents of a project
+fn build_model(documents: &[kdl::KdlDocument]) -> ArchModel {
+    let mut model = ArchModel::default();
+    let nodes = || documents.iter().flat_map(|doc| doc.nodes());
+
+    // The schema decides how ECU contents are read, so apply it before anything else
+    for kdl_node in nodes().filter(|n| n.name().value() == "schema") {
+        parse_schema(kdl_node, &mut model.schema);
     }
 
-    // Layout ECUs in a row below buses with proper spacing
-    let mut ecu_x = start_x;
-    for info in &ecu_nodes {
-        nodes.push(GraphNode {
-            id,
-            name: info.name.clone(),
-            node_type: info.node_type.clone(),
-            children: info.children.clone(),
-            x: px(ecu_x),
-            y: px(ecu_y),
-            drag_offset: None,
-            zoom: 1.0,
-            pan: point(px(0.0), px(0.0)),
-            selected: false,
-            container_offset: point(px(0.0), px(0.0)),
-            width: info.estimated_width,
-            height: info.estimated_height,
-            span: info.span,
-            graph_entity: None,
-        });
-        ecu_x += info.estimated_width + gap;
-        id += 1;
+    for kdl_node in nodes() {
+        // Top-level elements are identified by their type="..." property
+        match prop_str(kdl_node, "type") {
+            Some("bus") => model.buses.push(parse_bus(kdl_node)),
+            Some("ecu") => model.ecus.push(parse_ecu(kdl_node, &model.schema)),
+            Some("interface") => model.interfaces.push(parse_interface(kdl_node)),
+            _ => {}
+        }
     }
 
-    // Second pass: find interface connections from ECUs to buses
-    for kdl_node in doc.nodes() {
-        let ecu_name = kdl_node.name().to_string();
+    model
+}
+
+fn parse_bus(kdl_node: &kdl::KdlNode) -> Bus {
+    Bus {
+        name: kdl_node.name().value().to_string(),
+        protocol: child_str(kdl_node, "protocol"),
+        baudrate: child_u64(kdl_node, "baudrate"),
+        description: child_str(kdl_node, "description"),
+        position: prop_position(kdl_node),
+        span: span_of(kdl_node),
+    }
+}
 
-        // Check if this is an ECU
-        let is_ecu = kdl_node
+/// Allowed children per kind: `schema { partition "composition" "swc" }`
+fn parse_schema(kdl_node: &kdl::KdlNode, schema: &mut ContainmentSchema) {
+    for rule in child_nodes(kdl_node) {
+        let children = rule
             .entries()
             .iter()
-            .find(|e| {
-                e.name()
-                    .map(|n| n.to_string().as_str() == "type")
-                    .unwrap_or(false)
+            .filter(|e| e.name().is_none())
+            .filter_map(|e| e.value().as_string())
+            .map(str::to_string)
+            .collect();
+        schema.set(rule.name().value(), children);
+    }
+}
+
+fn parse_ecu(kdl_node: &kdl::KdlNode, schema: &ContainmentSchema) -> Ecu {
+    let interfaces = child_nodes(kdl_node)
+        .filter(|child| child.name().value() == "interface")
+        .map(|child| {
+            let bus = prop_entry(child, "bus");
+            Interface {
+                name: arg_name(child),
+                bus: bus.and_then(|e| e.value().as_string()).map(str::to_string),
+                bus_span: bus.map(span_of_entry),
+                span: span_of(child),
+            }
+        })
+        .collect();
+
+    Ecu {
+        name: kdl_node.name().value().to_string(),
+        vendor: child_str(kdl_node, "vendor"),
+        processor: child_str(kdl_node, "processor"),
+        memory_mb: child_u64(kdl_node, "memory_mb"),
+        description: child_str(kdl_node, "description"),
+        interfaces,
+        children: parse_elements(kdl_node, schema),
+        position: prop_position(kdl_node),
+        span: span_of(kdl_node),
+    }
+}
+
+/// Nested elements of any depth; placement rules are checked later by validation
+fn parse_elements(kdl_node: &kdl::KdlNode, schema: &ContainmentSchema) -> Vec<Element> {
+    child_nodes(kdl_node)
+        .filter_map(|child| match child.name().value() {
+            "swc" => Some(Element::Swc(parse_swc(child))),
+            kind if schema.is_known(kind) => {
+                Some(Element::Container(parse_container(child, schema)))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn parse_container(kdl_node: &kdl::KdlNode, schema: &ContainmentSchema) -> Container {
+    // Leaf children with a single value are kept as attributes (e.g., safety_level "ASIL-B")
+    let attributes = child_nodes(kdl_node)
+        .filter(|child| child.children().is_none() && !schema.is_known(child.name().value()))
+        .filter_map(|child| {
+            let value = child.entries().iter().find(|e| e.name().is_none())?.value();
+            let value = match value.as_string() {
+                Some(text) => text.to_string(),
+                None => value.to_string(),
+            };
+            Some((child.name().value().to_string(), value))
+        })
+        .collect();
+
+    Container {
+        kind: kdl_node.name().value().to_string(),
+        name: arg_name(kdl_node),
+        attributes,
+        children: parse_elements(kdl_node, schema),
+        span: span_of(kdl_node),
+    }
+}
+
+fn parse_swc(kdl_node: &kdl::KdlNode) -> Swc {
+    let mut swc = Swc {
+        name: arg_name(kdl_node),
+        description: child_str(kdl_node, "description"),
+        span: span_of(kdl_node),
+        ..Default::default()
+    };
+
+    for child in child_nodes(kdl_node) {
+        let node_name = child.name().value();
+        if node_name == "runnable" {
+            swc.runnables.push(Runnable {
+                name: arg_name(child),
+                period_ms: prop_u64(child, "period_ms"),
+                event: prop_str(child, "event").map(str::to_string),
+                span: span_of(child),
+            });
+        } else if let Some(direction) = PortDirection::from_node_name(node_name) {
+            swc.ports.push(parse_port(child, direction));
+        }
+    }
+
+    swc
+}
+
+fn parse_port(kdl_node: &kdl::KdlNode, direction: PortDirection) -> Port {
+    // Signal ports carry the signal inline: input_port "RPM" signal="RPM"
+    let signal = prop_entry(kdl_node, "signal").and_then(|e| {
+        e.value().as_string().map(|name| Signal {
+            name: name.to_string(),
+            span: span_of_entry(e),
+        })
+    });
+
+    // Service ports describe the service in child nodes
+    let members = |kind: &str| -> Vec<ServiceMember> {
+        child_nodes(kdl_node)
+            .filter(|child| child.name().value() == kind)
+            .map(|child| ServiceMember {
+                name: arg_name(child),
+                id: prop_u64(child, "id"),
+                span: span_of(child),
             })
-            .and_then(|e| e.value().as_string())
-            .map(|s| s == "ecu")
-            .unwrap_or(false);
+            .collect()
+    };
 
-        if !is_ecu {
-            continue;
+    Port {
+        name: arg_name(kdl_node),
+        direction,
+        signal,
+        interface: child_str(kdl_node, "interface"),
+        interface_span: child_node(kdl_node, "interface").map(span_of),
+        protocol: child_str(kdl_node, "protocol"),
+        service_id: child_u64(kdl_node, "service_id"),
+        service_id_span: child_node(kdl_node, "service_id").map(span_of),
+        methods: members("method"),
+        events: members("event"),
+        span: span_of(kdl_node),
+    }
+}
+
+fn parse_interface(kdl_node: &kdl::KdlNode) -> ServiceInterface {
+    let operations = |kind: &str| -> Vec<Operation> {
+        child_nodes(kdl_node)
+            .filter(|child| child.name().value() == kind)
+            .map(parse_operation)
+            .collect()
+    };
+
+    ServiceInterface {
+        name: kdl_node.name().value().to_string(),
+        description: child_str(kdl_node, "description"),
+        methods: operations("method"),
+        events: operations("event"),
+        position: prop_position(kdl_node),
+        span: span_of(kdl_node),
+    }
+}
+
+fn parse_operation(kdl_node: &kdl::KdlNode) -> Operation {
+    // Parameters are listed as: output "rpm" type="uint16"
+    let fields = |kind: &str| -> Vec<TypedField> {
+        child_nodes(kdl_node)
+            .filter(|child| child.name().value() == kind)
+            .map(|child| TypedField {
+                name: arg_name(child),
+                type_name: prop_str(child, "type").map(str::to_string),
+                span: span_of(child),
+            })
+            .collect()
+    };
+
+    Operation {
+        name: arg_name(kdl_node),
+        inputs: fields("input"),
+        outputs: fields("output"),
+        fields: fields("field"),
+        span: span_of(kdl_node),
+    }
+}
+
+/// Byte range covered by a KDL node
+fn span_of(kdl_node: &kdl::KdlNode) -> Span {
+    let s = kdl_node.span();
+    Span::new(s.offset(), s.offset() + s.len())
+}
+
+/// Byte range covered by a single entry (e.g., bus="CAN_Powertrain")
+fn span_of_entry(entry: &kdl::KdlEntry) -> Span {
+    let s = entry.span();
+    Span::new(s.offset(), s.offset() + s.len())
+}
+
+/// Iterate over the children of a KDL node (empty if it has no children block)
+fn child_nodes(kdl_node: &kdl::KdlNode) -> impl Iterator<Item = &kdl::KdlNode> {
+    kdl_node
+        .children()
+        .map(|children| children.nodes())
+        .unwrap_or_default()
+        .iter()
+}
+
+/// The name given as first positional argument (e.g., partition "GW_Routing")
+fn arg_name(kdl_node: &kdl::KdlNode) -> String {
+    kdl_node
+        .entries()
+        .iter()
+        .find(|e| e.name().is_none()) // positional argument has no name
+        .and_then(|e| e.value().as_string())
+        .unwrap_or("unnamed")
+        .to_string()
+}
+
+/// Entry of a named property (e.g., bus="CAN_Powertrain")
+fn prop_entry<'a>(kdl_node: &'a kdl::KdlNode, key: &str) -> Option<&'a kdl::KdlEntry> {
+    kdl_node
+        .entries()
+        .iter()
+        .find(|e| e.name().is_some_and(|n| n.value() == key))
+}
+
+fn prop<'a>(kdl_node: &'a kdl::KdlNode, key: &str) -> Option<&'a kdl::KdlValue> {
+    prop_entry(kdl_node, key).map(|e| e.value())
+}
+
+fn prop_str<'a>(kdl_node: &'a kdl::KdlNode, key: &str) -> Option<&'a str> {
+    prop(kdl_node, key).and_then(|v| v.as_string())
+}
+
+fn prop_u64(kdl_node: &kdl::KdlNode, key: &str) -> Option<u64> {
+    prop(kdl_node, key)
+        .and_then(|v| v.as_integer())
+        .and_then(|v| u64::try_from(v).ok())
+}
+
+/// Layout hint of a top-level element: `EngineECU type="ecu" x=120 y=300`
+fn prop_position(kdl_node: &kdl::KdlNode) -> Option<(f32, f32)> {
+    let coordinate = |key: &str| {
+        let value = prop(kdl_node, key)?;
+        match value.as_integer() {
+            Some(v) => Some(v as f32),
+            None => value.as_float().map(|v| v as f32),
         }
+    };
+    Some((coordinate("x")?, coordinate("y")?))
+}
 
-        // Get the ECU's index
-        let Some(&ecu_index) = node_name_to_index.get(&ecu_name) else {
-            continue;
+/// First child node with the given name
+fn child_node<'a>(kdl_node: &'a kdl::KdlNode, name: &str) -> Option<&'a kdl::KdlNode> {
+    child_nodes(kdl_node).find(|child| child.name().value() == name)
+}
+
+/// First argument of a child node used as attribute (e.g., protocol "CAN-FD")
+fn child_arg<'a>(kdl_node: &'a kdl::KdlNode, name: &str) -> Option<&'a kdl::KdlValue> {
+    child_node(kdl_node, name)
+        .and_then(|child| child.entries().iter().find(|e| e.name().is_none()))
+        .map(|e| e.value())
+}
+
+fn child_str(kdl_node: &kdl::KdlNode, name: &str) -> Option<String> {
+    child_arg(kdl_node, name)
+        .and_then(|v| v.as_string())
+        .map(str::to_string)
+}
+
+fn child_u64(kdl_node: &kdl::KdlNode, name: &str) -> Option<u64> {
+    child_arg(kdl_node, name)
+        .and_then(|v| v.as_integer())
+        .and_then(|v| u64::try_from(v).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Directory of the test documents; it does not exist, so paths are not canonicalized
+    const DIR: &str = "/arcivis-tests";
+
+    /// Parse the first of `files` as the root document, reading includes from the others
+    fn parse(files: &[(&str, &str)]) -> ParseResult {
+        let read = |path: &Path| {
+            files
+                .iter()
+                .find(|(name, _)| Path::new(DIR).join(name) == path)
+                .map(|(_, text)| text.to_string())
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "not found"))
         };
+        let (name, text) = files[0];
+        parse_project(Some(Path::new(DIR).join(name).as_path()), text, read)
+    }
 
-        // Look for interface children with bus= attribute
-        if let Some(children) = kdl_node.children() {
-            for child in children.nodes() {
-                if child.name().to_string() == "interface" {
-                    // Find the bus= attribute
-                    if let Some(bus_name) = child
-                        .entries()
-                        .iter()
-                        .find(|e| {
-                            e.name()
-                                .map(|n| n.to_string().as_str() == "bus")
-                                .unwrap_or(false)
-                        })
-                        .and_then(|e| e.value().as_string())
-                    {
-                        // Create edge from ECU to bus
-                        if let Some(&bus_index) = node_name_to_index.get(bus_name) {
-                            edges.push(GraphEdge::new(ecu_index, bus_index));
+    fn messages(result: &ParseResult) -> Vec<&str> {
+        result.load_errors().map(|d| d.message.as_str()).collect()
+    }
+
+    #[test]
+    fn test_attributes() {
+        let result = parse_model(
+            r#"
+            CAN type="bus" {
+                protocol "CAN"
+                baudrate 500000
+                description "Powertrain bus"
+            }
+            EngineECU type="ecu" {
+                vendor "Bosch"
+                processor "TC397"
+                memory_mb 8
+                partition "Safety" {
+                    safety_level "ASIL-B"
+                    swc "Injection" {
+                        description "Fuel injection"
+                        runnable "Main" period_ms=10
+                        runnable "OnCrank" event="CrankPulse"
+                        provided_port "Speed" {
+                            protocol "SOME/IP"
+                            service_id 4660
+                            method "GetRpm" id=1
                         }
                     }
                 }
             }
-        }
+            "#,
+        );
+        let model = result.model.unwrap();
+
+        let bus = &model.buses[0];
+        assert_eq!(bus.protocol.as_deref(), Some("CAN"));
+        assert_eq!(bus.baudrate, Some(500000));
+        assert_eq!(bus.description.as_deref(), Some("Powertrain bus"));
+
+        let ecu = &model.ecus[0];
+        assert_eq!(ecu.vendor.as_deref(), Some("Bosch"));
+        assert_eq!(ecu.processor.as_deref(), Some("TC397"));
+        assert_eq!(ecu.memory_mb, Some(8));
+        let Element::Container(partition) = &ecu.children[0] else {
+            panic!("expected a partition");
+        };
+        assert_eq!(
+            partition.attributes,
+            [("safety_level".to_string(), "ASIL-B".to_string())]
+        );
+
+        let swc = ecu.swcs()[0];
+        assert_eq!(swc.description.as_deref(), Some("Fuel injection"));
+        let runnables: Vec<_> = swc
+            .runnables
+            .iter()
+            .map(|r| (r.name.as_str(), r.period_ms, r.event.as_deref()))
+            .collect();
+        assert_eq!(
+            runnables,
+            [
+                ("Main", Some(10), None),
+                ("OnCrank", None, Some("CrankPulse"))
+            ]
+        );
+
+        let port = &swc.ports[0];
+        assert_eq!(port.protocol.as_deref(), Some("SOME/IP"));
+        assert_eq!(port.service_id, Some(4660));
+        assert_eq!(port.methods[0].name, "GetRpm");
+        assert_eq!(port.methods[0].id, Some(1));
     }
 
-    (nodes, edges)
-}
-
-/// Extract partition and swc children from a KDL node
-fn extract_node_children(kdl_node: &kdl::KdlNode) -> Vec<NodeChild> {
-    let mut children = Vec::new();
-
-    if let Some(kdl_children) = kdl_node.children() {
-        for child in kdl_children.nodes() {
-            let node_name = child.name().to_string();
-
-            // Check if this is a partition node (node name is "partition")
-            if node_name == "partition" {
-                // The partition name is the first positional argument (e.g., partition "GW_Routing")
-                let partition_name = child
-                    .entries()
-                    .iter()
-                    .find(|e| e.name().is_none()) // positional argument has no name
-                    .and_then(|e| e.value().as_string())
-                    .map(|s| s.to_string())
-                    .unwrap_or_else(|| "unnamed".to_string());
-
-                // Extract swcs inside the partition
-                let swc_children = extract_swcs(child);
-
-                children.push(NodeChild {
-                    name: partition_name,
-                    kind: "partition".to_string(),
-                    children: swc_children,
-                });
+    #[test]
+    fn test_write_positions() {
+        let text = r#"
+            // Powertrain
+            CAN type="bus" x=10 y=20
+            EngineECU type="ecu" {
+                vendor "Bosch"
             }
-        }
+            "#;
+        let model = parse_model(text).model.unwrap();
+        let positions = [
+            (model.buses[0].span.start, (40.0, 60.4)),
+            (model.ecus[0].span.start, (120.0, -80.6)),
+        ];
+
+        let text = write_positions(text, &positions).unwrap();
+        assert!(text.contains("// Powertrain"));
+        assert!(text.contains("vendor \"Bosch\""));
+        // Existing hints are replaced, not repeated
+        assert_eq!(text.matches("x=").count(), 2);
+
+        let model = parse_model(&text).model.unwrap();
+        assert_eq!(model.buses[0].position, Some((40.0, 60.0)));
+        assert_eq!(model.ecus[0].position, Some((120.0, -81.0)));
+        assert_eq!(model.ecus[0].vendor.as_deref(), Some("Bosch"));
     }
 
-    children
-}
+    #[test]
+    fn test_include() {
+        let result = parse(&[
+            (
+                "main.kdl",
+                "include \"powertrain.kdl\"\nGateway type=\"ecu\"\n",
+            ),
+            ("powertrain.kdl", "CAN type=\"bus\"\n"),
+        ]);
+        assert!(!result.has_errors());
+        assert_eq!(result.sources.files.len(), 2);
+
+        let model = result.model.unwrap();
+        assert_eq!(model.buses.len(), 1);
+        assert_eq!(model.buses[0].name, "CAN");
+        assert_eq!(model.ecus.len(), 1);
+        assert_eq!(model.ecus[0].name, "Gateway");
+    }
 
-/// Extract SWC children from a partition node
-fn extract_swcs(partition_node: &kdl::KdlNode) -> Vec<NodeChild> {
-    let mut swcs = Vec::new();
+    #[test]
+    fn test_include_loaded_once() {
+        let result = parse(&[
+            ("main.kdl", "include \"a.kdl\"\ninclude \"b.kdl\"\n"),
+            ("a.kdl", "include \"common.kdl\"\n"),
+            ("b.kdl", "include \"common.kdl\"\n"),
+            ("common.kdl", "CAN type=\"bus\"\n"),
+        ]);
+        assert!(!result.has_errors());
+        assert_eq!(result.sources.files.len(), 4);
+        assert_eq!(result.model.unwrap().buses.len(), 1);
+    }
 
-    if let Some(kdl_children) = partition_node.children() {
-        for child in kdl_children.nodes() {
-            let node_name = child.name().to_string();
+    #[test]
+    fn test_include_cycle() {
+        let result = parse(&[
+            ("main.kdl", "include \"a.kdl\"\nCAN type=\"bus\"\n"),
+            ("a.kdl", "include \"main.kdl\"\n"),
+        ]);
+        assert_eq!(
+            messages(&result),
+            ["Include cycle: main.kdl → a.kdl → main.kdl"]
+        );
+
+        // The error points at the include closing the cycle
+        let error = result.load_errors().next().unwrap();
+        let (file, _) = result.sources.locate(error.span).unwrap();
+        assert_eq!(file.name(), "a.kdl");
+
+        // The documents read before are still used
+        assert_eq!(result.model.unwrap().buses.len(), 1);
+
+        let result = parse(&[("main.kdl", "include \"main.kdl\"\n")]);
+        assert_eq!(messages(&result), ["Include cycle: main.kdl → main.kdl"]);
+    }
 
-            // Check if this is an swc node (node name is "swc")
-            if node_name == "swc" {
-                // The swc name is the first positional argument (e.g., swc "CanRouter")
-                let swc_name = child
-                    .entries()
-                    .iter()
-                    .find(|e| e.name().is_none()) // positional argument has no name
-                    .and_then(|e| e.value().as_string())
-                    .map(|s| s.to_string())
-                    .unwrap_or_else(|| "unnamed".to_string());
-
-                swcs.push(NodeChild {
-                    name: swc_name,
-                    kind: "swc".to_string(),
-                    children: Vec::new(),
-                });
-            }
-        }
+    #[test]
+    fn test_include_missing() {
+        let result = parse(&[("main.kdl", "CAN type=\"bus\"\ninclude \"missing.kdl\"\n")]);
+        assert_eq!(
+            messages(&result),
+            ["Cannot read included file `missing.kdl`: not found"]
+        );
+        let error = result.load_errors().next().unwrap();
+        let (file, span) = result.sources.locate(error.span).unwrap();
+        assert_eq!(file.name(), "main.kdl");
+        assert!(file.text[span.start..].starts_with("include"));
+
+        let result = parse(&[("main.kdl", "include\n")]);
+        assert_eq!(messages(&result), ["`include` needs the path of a file"]);
+    }
+
+    #[test]
+    fn test_syntax_error_in_include() {
+        let result = parse(&[
+            ("main.kdl", "include \"broken.kdl\"\n"),
+            ("broken.kdl", "CAN type=\"bus\" {\n"),
+        ]);
+        assert!(result.model.is_none());
+        let error = result.load_errors().next().unwrap();
+        let (file, _) = result.sources.locate(error.span).unwrap();
+        assert_eq!(file.name(), "broken.kdl");
     }
 
-    swcs
+    #[test]
+    fn test_spans_across_files() {
+        let main = "include \"powertrain.kdl\"\nCAN type=\"bus\"\n";
+        let result = parse(&[("main.kdl", main), ("powertrain.kdl", "CAN type=\"bus\"\n")]);
+
+        // Each file starts behind the previous one
+        let powertrain = Path::new(DIR).join("powertrain.kdl");
+        let base = main.len() + 1;
+        assert_eq!(result.sources.offset_in(None, 8), Some(8));
+        assert_eq!(
+            result.sources.offset_in(Some(powertrain.as_path()), 4),
+            Some(base + 4)
+        );
+
+        // A validation error, not a load error, located in the included file
+        assert!(result.has_errors());
+        assert_eq!(result.load_errors().count(), 0);
+        let error = result.diagnostics.iter().find(|d| d.is_error()).unwrap();
+        assert_eq!(error.message, "`CAN` is defined more than once");
+        let (file, span) = result.sources.locate(error.span).unwrap();
+        assert_eq!(file.name(), "powertrain.kdl");
+        assert_eq!(span.start + base, error.span.start);
+        assert!(file.text[span.start..].trim_start().starts_with("CAN"));
+
+        let model = result.model.unwrap();
+        let (file, _) = result.sources.locate(model.buses[0].span).unwrap();
+        assert_eq!(file.name(), "main.kdl");
+    }
 }
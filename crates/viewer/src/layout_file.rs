@@ -0,0 +1,271 @@
+//! Sidecar file keeping the hand-tuned arrangement of a diagram next to its model
+//!
+//! `vehicle.kdl` gets a `vehicle.layout.json`, a project directory an `arcivis.layout.json`.
+//! Entries are sorted and rounded so the file can be committed and diffed.
+
+use graphview::{
+    DagreAlign, DagreRanker, DagreSettings, LayoutMode, LayoutSnapshot, NodeLayout, RankDirection,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Layout file of a model file, or of a project directory
+pub fn layout_path(model: &Path) -> PathBuf {
+    if model.is_dir() {
+        model.join("arcivis.layout.json")
+    } else {
+        model.with_extension("layout.json")
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct LayoutFile {
+    layout_mode: String,
+    zoom: f32,
+    pan: [f32; 2],
+    /// Options of the Dagre layout, left out while they are the defaults
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dagre: Option<DagreEntry>,
+    /// Keyed by the qualified node name
+    nodes: BTreeMap<String, NodeEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct NodeEntry {
+    x: f32,
+    y: f32,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pinned: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    collapsed: bool,
+    /// Collapsed nested children as `/`-separated name paths, e.g. `Hypervisor/VM1`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    collapsed_children: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct DagreEntry {
+    /// `TB`, `BT`, `LR` or `RL`
+    rank_dir: String,
+    node_sep: f32,
+    rank_sep: f32,
+    edge_sep: f32,
+    /// `UL`, `UR`, `DL` or `DR`; balanced when missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    align: Option<String>,
+    /// `network-simplex`, `tight-tree` or `longest-path`
+    ranker: String,
+}
+
+impl DagreEntry {
+    fn new(settings: &DagreSettings) -> Self {
+        let ranker = match settings.ranker {
+            DagreRanker::NetworkSimplex => "network-simplex",
+            DagreRanker::TightTree => "tight-tree",
+            DagreRanker::LongestPath => "longest-path",
+        };
+        Self {
+            rank_dir: settings.rank_direction.label().to_string(),
+            node_sep: round(settings.node_separation),
+            rank_sep: round(settings.rank_separation),
+            edge_sep: round(settings.edge_separation),
+            align: settings.align.map(|align| align.label().to_string()),
+            ranker: ranker.to_string(),
+        }
+    }
+
+    /// Settings of the entry; unknown names fall back to the defaults
+    fn settings(&self) -> DagreSettings {
+        let defaults = DagreSettings::default();
+        DagreSettings {
+            rank_direction: RankDirection::ALL
+                .into_iter()
+                .find(|direction| direction.label() == self.rank_dir)
+                .unwrap_or(defaults.rank_direction),
+            node_separation: self.node_sep,
+            rank_separation: self.rank_sep,
+            edge_separation: self.edge_sep,
+            align: DagreAlign::ALL
+                .into_iter()
+                .find(|align| Some(align.label()) == self.align.as_deref()),
+            ranker: match self.ranker.as_str() {
+                "tight-tree" => DagreRanker::TightTree,
+                "longest-path" => DagreRanker::LongestPath,
+                _ => DagreRanker::NetworkSimplex,
+            },
+        }
+    }
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+/// Keep one decimal, enough for positions on screen
+fn round(value: f32) -> f32 {
+    (value * 10.0).round() / 10.0
+}
+
+pub fn load(path: &Path) -> io::Result<LayoutSnapshot> {
+    let text = std::fs::read_to_string(path)?;
+    let file: LayoutFile = serde_json::from_str(&text)?;
+    let layout_mode = match file.layout_mode.as_str() {
+        "dagre" => LayoutMode::Dagre,
+        "arcivis" => LayoutMode::ArciVis,
+        "bus" => LayoutMode::Bus,
+        _ => LayoutMode::Force,
+    };
+    let nodes = file
+        .nodes
+        .into_iter()
+        .map(|(key, entry)| {
+            let layout = NodeLayout {
+                x: entry.x,
+                y: entry.y,
+                pinned: entry.pinned,
+                collapsed: entry.collapsed,
+                collapsed_children: entry
+                    .collapsed_children
+                    .iter()
+                    .map(|path| path.split('/').map(str::to_string).collect())
+                    .collect(),
+            };
+            (key, layout)
+        })
+        .collect();
+    Ok(LayoutSnapshot {
+        nodes,
+        zoom: file.zoom,
+        pan: (file.pan[0], file.pan[1]),
+        layout_mode,
+        dagre: file.dagre.map(|entry| entry.settings()).unwrap_or_default(),
+    })
+}
+
+pub fn save(path: &Path, snapshot: &LayoutSnapshot) -> io::Result<()> {
+    let layout_mode = match snapshot.layout_mode {
+        LayoutMode::Force => "force",
+        LayoutMode::Dagre => "dagre",
+        LayoutMode::ArciVis => "arcivis",
+        LayoutMode::Bus => "bus",
+    };
+    let nodes = snapshot
+        .nodes
+        .iter()
+        .map(|(key, layout)| {
+            let entry = NodeEntry {
+                x: round(layout.x),
+                y: round(layout.y),
+                pinned: layout.pinned,
+                collapsed: layout.collapsed,
+                collapsed_children: layout
+                    .collapsed_children
+                    .iter()
+                    .map(|path| path.join("/"))
+                    .collect(),
+            };
+            (key.clone(), entry)
+        })
+        .collect();
+    let file = LayoutFile {
+        layout_mode: layout_mode.to_string(),
+        zoom: (snapshot.zoom * 100.0).round() / 100.0,
+        pan: [round(snapshot.pan.0), round(snapshot.pan.1)],
+        dagre: (snapshot.dagre != DagreSettings::default())
+            .then(|| DagreEntry::new(&snapshot.dagre)),
+        nodes,
+    };
+    let mut text = serde_json::to_string_pretty(&file)?;
+    text.push('\n');
+    std::fs::write(path, text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A file in the temp directory, named after the test so tests can run in parallel
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "arcivis-{}-{}.layout.json",
+            name,
+            std::process::id()
+        ))
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let path = temp_path("round-trip");
+        let mut snapshot = LayoutSnapshot {
+            zoom: 1.25,
+            pan: (10.5, -20.0),
+            layout_mode: LayoutMode::Bus,
+            ..Default::default()
+        };
+        snapshot.nodes.insert(
+            "EngineECU".to_string(),
+            NodeLayout {
+                x: 120.0,
+                y: 40.5,
+                pinned: true,
+                collapsed: false,
+                collapsed_children: vec![vec!["Hypervisor".to_string(), "VM1".to_string()]],
+            },
+        );
+        snapshot.nodes.insert(
+            "CAN".to_string(),
+            NodeLayout {
+                x: -30.0,
+                y: 200.0,
+                collapsed: true,
+                ..Default::default()
+            },
+        );
+
+        save(&path, &snapshot).unwrap();
+        let loaded = load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap(), snapshot);
+    }
+
+    #[test]
+    fn test_dagre_settings() {
+        let path = temp_path("dagre");
+        let mut snapshot = LayoutSnapshot {
+            zoom: 1.0,
+            layout_mode: LayoutMode::Dagre,
+            ..Default::default()
+        };
+        save(&path, &snapshot).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        // Left out while they are the defaults
+        assert!(!text.contains("dagre\":"));
+
+        snapshot.dagre = DagreSettings {
+            rank_direction: RankDirection::LeftToRight,
+            node_separation: 40.0,
+            rank_separation: 80.5,
+            edge_separation: 5.0,
+            align: Some(DagreAlign::DownRight),
+            ranker: DagreRanker::LongestPath,
+        };
+        save(&path, &snapshot).unwrap();
+        let loaded = load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap(), snapshot);
+    }
+
+    #[test]
+    fn test_load_missing_or_corrupt() {
+        let path = temp_path("corrupt");
+        let error = load(&path).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+
+        std::fs::write(&path, "{ layout_mode: bus }").unwrap();
+        let error = load(&path).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
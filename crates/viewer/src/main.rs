@@ -1,44 +1,87 @@
-use gpui::*;
+use gpui::{prelude::FluentBuilder, *};
 use gpui_component::{
-    ActiveTheme,
-    highlighter::Language,
+    ActiveTheme, WindowExt, h_flex,
+    highlighter::{self, DiagnosticSeverity, Language},
     input::{Input, InputEvent, InputState, TabSize},
+    notification::Notification,
     resizable::{h_resizable, resizable_panel},
+    v_flex,
 };
 use gpui_component_assets::Assets;
-use gpui_component_story::Open;
-use graphview::{EdgeRouting, Graph, NodeSelected};
+use gpui_component_story::{CloseWindow, Open, Save, SaveAs, ToggleLayoutInModel};
+use graphview::{
+    EdgeRouting, Graph, JumpToSpan, LayoutChanged, ModelEdit, ModelHistory, NodeSelected,
+    SelectionChanged, StaleState, ViewChanged,
+};
 use lsp_types::Position;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use tracing::{error, info};
 
+mod connections;
+mod diagnostics;
+mod edit;
 mod kdl;
-use kdl::parse_kdl_model;
+mod layout_file;
+mod model;
+mod problems;
+mod project;
+mod projection;
+mod sources;
+mod text;
+mod validate;
+mod watch;
+use diagnostics::Severity;
+use edit::{FileChange, TextEdit};
+use kdl::{ParseResult, parse_model, parse_project, parse_workspace, write_positions};
+use model::Span;
+use problems::{JumpToSource, ProblemsPanel};
+use project::{OpenFile, ProjectPanel};
+use projection::project;
+use sources::Sources;
+use text::{line_char_to_offset, offset_to_position, offset_to_utf16};
+use watch::FileWatcher;
 
 pub struct Example {
     input_state: Entity<InputState>,
     graph: Entity<Graph>,
+    problems: Entity<ProblemsPanel>,
     last_cursor_pos: Option<Position>,
+    /// File shown in the editor, `None` for the built-in example
+    document: Option<PathBuf>,
+    /// Document the model is loaded from; it may include the one in the editor
+    root: Option<PathBuf>,
+    /// Edited contents of files that are not shown in the editor right now
+    buffers: HashMap<PathBuf, String>,
+    /// Files of the current model, to map spans to files and back
+    sources: Sources,
+    /// File tree of the opened directory; in project mode the model spans all its files
+    project: Option<Entity<ProjectPanel>>,
+    /// Files opened in tabs above the editor
+    tabs: Vec<PathBuf>,
+    /// Contents of the files as last read from or written to disk
+    saved: HashMap<PathBuf, String>,
+    /// Whether any file of the model has unsaved changes
+    edited: bool,
+    /// Reloads files of the model changed by other programs
+    watcher: FileWatcher,
+    /// Sidecar file the arrangement of the graph is kept in
+    layout_path: Option<PathBuf>,
+    /// Pending write of the layout file, replaced on every change
+    layout_save: Option<Task<()>>,
+    /// Zoom or pan changed since the layout file was written, kept with the next write
+    view_changed: bool,
+    /// Write node positions into the model as `x=`/`y=` hints when they change
+    layout_in_model: bool,
+    /// Node positions when the model was last projected, to find the nodes moved since
+    placed: HashMap<String, (f32, f32)>,
+    /// Edits made in the graph, undone and redone from the graph's history
+    model_undo: Vec<Vec<FileChange>>,
+    model_redo: Vec<Vec<FileChange>>,
     _subscriptions: Vec<Subscription>,
 }
 
-/// Convert line and character position to byte offset in the text
-fn line_char_to_offset(text: &str, line: usize, character: usize) -> usize {
-    let mut current_line = 0;
-    let mut current_char = 0;
-    for (i, ch) in text.char_indices() {
-        if current_line == line && current_char == character {
-            return i;
-        }
-        if ch == '\n' {
-            current_line += 1;
-            current_char = 0;
-        } else {
-            current_char += 1;
-        }
-    }
-    text.len() // if beyond the text
-}
-
 const EXAMPLE: &str = include_str!("../tests/model/vehicle.kdl");
 
 impl Example {
@@ -57,7 +100,8 @@ impl Example {
         });
 
         // Parse the KDL content and create nodes/edges from ECUs and buses
-        let (nodes, edges) = parse_kdl_model(EXAMPLE);
+        let result = parse_model(EXAMPLE);
+        let (nodes, edges) = result.model.as_ref().map(project).unwrap_or_default();
         let node_count = nodes.len();
 
         let graph = cx.new(|_cx| {
@@ -72,30 +116,27 @@ impl Example {
         });
 
         // Update the graph model to set up subscriptions and graph_entity references
-        let nodes_for_update = parse_kdl_model(EXAMPLE).0;
-        let edges_for_update = parse_kdl_model(EXAMPLE).1;
-        graph.update(cx, |graph, cx| {
-            graph.update_model(nodes_for_update, edges_for_update, cx);
-        });
+        if let Some(model) = &result.model {
+            let (nodes_for_update, edges_for_update) = project(model);
+            graph.update(cx, |graph, cx| {
+                graph.update_model(nodes_for_update, edges_for_update, cx);
+            });
+        }
+
+        let problems = cx.new(|_| ProblemsPanel::new());
 
         // Subscribe to input changes and update the graph
-        let graph_for_sub = graph.clone();
-        let input_state_for_graph = input_state.clone();
         let _subscriptions = vec![
-            cx.subscribe(&input_state, move |_this, input, event: &InputEvent, cx| {
-                if let InputEvent::Change = event {
-                    let content = input.read(cx).value();
-                    let (nodes, edges) = parse_kdl_model(&content);
-                    // Only update if we have valid nodes (KDL parsed successfully with content)
-                    if !nodes.is_empty() {
-                        graph_for_sub.update(cx, |graph, cx| {
-                            graph.update_model(nodes, edges, cx);
-                        });
-                    } else {
-                        error!("Document has errors, not updating graph!")
+            cx.subscribe_in(
+                &input_state,
+                window,
+                move |this, _input, event: &InputEvent, window, cx| {
+                    if let InputEvent::Change = event {
+                        this.update_document(cx);
+                        this.update_edited(window, cx);
                     }
-                }
-            }),
+                },
+            ),
             cx.observe(&input_state, move |this, input, cx| {
                 let pos = input.read(cx).cursor_position();
                 if Some(pos) != this.last_cursor_pos {
@@ -103,6 +144,11 @@ impl Example {
                     let content = input.read(cx).value();
                     let cursor =
                         line_char_to_offset(&content, pos.line as usize, pos.character as usize);
+                    // Node spans live in the offset space shared by all files of the model
+                    let Some(cursor) = this.sources.offset_in(this.document.as_deref(), cursor)
+                    else {
+                        return;
+                    };
                     info!("Cursor moved to byte position: {}", cursor);
                     this.graph.update(cx, |graph, cx| {
                         // Deselect all
@@ -116,7 +162,10 @@ impl Example {
                                 if s <= cursor && cursor <= e {
                                     let node_name =
                                         cx.read_entity(node_entity, |n, _| n.name.clone());
-                                    cx.update_entity(node_entity, |node, _| node.selected = true);
+                                    cx.update_entity(node_entity, |node, _| {
+                                        node.selected = true;
+                                        node.selected_child = node.child_path_at_offset(cursor);
+                                    });
                                     info!("Selected node: {}", node_name);
                                     break;
                                 }
@@ -125,27 +174,1057 @@ impl Example {
                     });
                 }
             }),
-            cx.subscribe(&graph, move |_this, _graph, event: &NodeSelected, cx| {
-                info!(
-                    "Node selected in graph: id={}, span={:?}",
-                    event.node_id, event.span
-                );
-                // Set text selection to event.span
-                if let Some((start, end)) = event.span {
-                    info!("Setting text selection to {}..{}", start, end);
-                    input_state_for_graph.update(cx, |input, cx| {
-                        input.set_selection_range(start, end, cx);
-                    });
-                }
-            }),
+            cx.subscribe_in(
+                &graph,
+                window,
+                move |this, _graph, event: &NodeSelected, window, cx| {
+                    info!(
+                        "Node selected in graph: id={}, span={:?}",
+                        event.node_id, event.span
+                    );
+                    // Select event.span, in the file the node is defined in
+                    if let Some((start, end)) = event.span {
+                        info!("Setting text selection to {}..{}", start, end);
+                        this.reveal(Span::new(start, end), window, cx);
+                    }
+                },
+            ),
+            cx.subscribe_in(
+                &graph,
+                window,
+                move |this, _graph, event: &SelectionChanged, window, cx| {
+                    this.reveal_all(&event.spans, window, cx);
+                },
+            ),
+            cx.subscribe_in(
+                &graph,
+                window,
+                move |this, _graph, event: &JumpToSpan, window, cx| {
+                    let (start, end) = event.span;
+                    this.reveal(Span::new(start, end), window, cx);
+                },
+            ),
+            cx.subscribe_in(
+                &graph,
+                window,
+                move |this, _graph, _: &LayoutChanged, window, cx| {
+                    this.schedule_layout_save(cx);
+                    if this.layout_in_model {
+                        this.write_layout_hints(window, cx);
+                    }
+                },
+            ),
+            cx.subscribe_in(
+                &graph,
+                window,
+                move |this, _graph, _: &ViewChanged, _window, _cx| {
+                    this.view_changed = true;
+                },
+            ),
+            cx.subscribe_in(
+                &graph,
+                window,
+                move |this, _graph, event: &ModelEdit, window, cx| {
+                    this.on_model_edit(event, window, cx);
+                },
+            ),
+            cx.subscribe_in(
+                &graph,
+                window,
+                move |this, _graph, event: &ModelHistory, window, cx| {
+                    this.on_model_history(*event, window, cx);
+                },
+            ),
+            cx.subscribe_in(
+                &problems,
+                window,
+                move |this, _problems, event: &JumpToSource, window, cx| {
+                    this.reveal(event.span, window, cx);
+                },
+            ),
         ];
 
-        Self {
+        let (watcher, changes) = FileWatcher::new();
+        cx.spawn_in(window, async move |this, window| {
+            while let Ok(mut paths) = changes.recv().await {
+                // Editors and scripts often write a file in several steps, wait for the rest
+                window
+                    .background_executor()
+                    .timer(Duration::from_millis(100))
+                    .await;
+                while let Ok(more) = changes.try_recv() {
+                    paths.extend(more);
+                }
+                let updated = this.update_in(window, |this, window, cx| {
+                    this.on_files_changed(paths, window, cx);
+                });
+                if updated.is_err() {
+                    break;
+                }
+            }
+        })
+        .detach();
+
+        // Ask before closing the window with unsaved changes
+        let weak = cx.weak_entity();
+        window.on_window_should_close(cx, move |window, cx| {
+            weak.update(cx, |this, cx| this.confirm_close(window, cx))
+                .unwrap_or(true)
+        });
+
+        let mut this = Self {
             input_state,
             graph,
+            problems,
             last_cursor_pos: None,
+            document: None,
+            root: None,
+            buffers: HashMap::new(),
+            sources: result.sources.clone(),
+            project: None,
+            tabs: Vec::new(),
+            saved: HashMap::new(),
+            edited: false,
+            watcher,
+            layout_path: None,
+            layout_save: None,
+            view_changed: false,
+            layout_in_model: false,
+            placed: HashMap::new(),
+            model_undo: Vec::new(),
+            model_redo: Vec::new(),
             _subscriptions,
+        };
+        this.show_diagnostics(&result, cx);
+        this
+    }
+
+    /// Parse the model from its root document, reading open files from the editor buffers
+    fn parse(&self, cx: &App) -> ParseResult {
+        let content = self.input_state.read(cx).value().to_string();
+        let mut buffers = self.buffers.clone();
+        if let Some(document) = &self.document {
+            buffers.insert(document.clone(), content.clone());
+        }
+        let read = |path: &Path| match buffers.get(path) {
+            Some(text) => Ok(text.clone()),
+            None => std::fs::read_to_string(path),
+        };
+
+        if let Some(project) = &self.project {
+            return parse_workspace(project.read(cx).files(), read);
+        }
+
+        let root = self
+            .root
+            .as_ref()
+            .filter(|root| self.document.as_ref() != Some(*root));
+        match root.map(|root| (root, read(root))) {
+            Some((root, Ok(root_content))) => {
+                parse_project(Some(root.as_path()), &root_content, read)
+            }
+            // Without a separate root, the document in the editor is the root
+            _ => parse_project(self.document.as_deref(), &content, read),
+        }
+    }
+
+    /// Select a span in the editor, switching to the file that contains it
+    fn reveal(&mut self, span: Span, window: &mut Window, cx: &mut Context<Self>) {
+        let Some((file, local)) = self.sources.locate(span) else {
+            return;
+        };
+        let other_file = match &file.path {
+            Some(path) if file.path != self.document => Some((path.clone(), file.text.clone())),
+            _ => None,
+        };
+        if let Some((path, text)) = other_file {
+            // The loaded text already contains unsaved edits of that file
+            self.buffers.remove(&path);
+            self.show_file(path, text, window, cx);
+        }
+        self.input_state.update(cx, |input, cx| {
+            input.set_selection_range(local.start, local.end, cx);
+        });
+    }
+
+    /// Select the range covering several elements: in the file shown in the editor if any of
+    /// them is there, otherwise in the file of the first one
+    fn reveal_all(
+        &mut self,
+        spans: &[(usize, usize)],
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let located: Vec<(usize, Span)> = spans
+            .iter()
+            .map(|&(start, end)| Span::new(start, end))
+            .filter_map(|span| Some((self.sources.locate(span)?.0.base, span)))
+            .collect();
+        let Some(&(first, _)) = located.first() else {
+            return;
+        };
+        let base = self
+            .sources
+            .files
+            .iter()
+            .find(|file| file.path == self.document)
+            .map(|file| file.base)
+            .filter(|shown| located.iter().any(|(base, _)| base == shown))
+            .unwrap_or(first);
+        let in_file = located
+            .iter()
+            .filter(|(b, _)| *b == base)
+            .map(|(_, span)| span);
+        let start = in_file
+            .clone()
+            .map(|span| span.start)
+            .min()
+            .unwrap_or_default();
+        let end = in_file.map(|span| span.end).max().unwrap_or_default();
+        self.reveal(Span::new(start, end), window, cx);
+    }
+
+    /// Show another file of the model in the editor, keeping the edits of the current one
+    fn show_file(
+        &mut self,
+        path: PathBuf,
+        text: String,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if let Some(document) = self.document.take() {
+            let content = self.input_state.read(cx).value().to_string();
+            self.buffers.insert(document, content);
+        }
+        if !self.tabs.contains(&path) {
+            self.tabs.push(path.clone());
+        }
+        if !self.saved.contains_key(&path) {
+            let on_disk = std::fs::read_to_string(&path).unwrap_or_default();
+            self.saved.insert(path.clone(), on_disk);
+        }
+        self.document = Some(path);
+        self.input_state.update(cx, |input, cx| {
+            input.set_value(text, window, cx);
+        });
+        self.update_document(cx);
+        self.update_edited(window, cx);
+    }
+
+    /// Whether `text` differs from what is stored for the file, the built-in example
+    /// counts as saved until it is edited
+    fn is_modified(&self, path: Option<&PathBuf>, text: &str) -> bool {
+        match path {
+            Some(path) => self.saved.get(path).is_none_or(|saved| saved != text),
+            None => text != EXAMPLE,
+        }
+    }
+
+    fn has_unsaved_changes(&self, cx: &App) -> bool {
+        let content = self.input_state.read(cx).value();
+        self.is_modified(self.document.as_ref(), &content)
+            || self
+                .buffers
+                .iter()
+                .any(|(path, text)| self.is_modified(Some(path), text))
+    }
+
+    /// Show in the title bar whether there are unsaved changes
+    fn update_edited(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let edited = self.has_unsaved_changes(cx);
+        if edited != self.edited {
+            self.edited = edited;
+            gpui_component_story::set_window_edited(edited, window, cx);
+        }
+    }
+
+    fn on_action_save(&mut self, _: &Save, window: &mut Window, cx: &mut Context<Self>) {
+        match self.document.clone() {
+            Some(path) => self.save_to(path, window, cx),
+            None => self.on_action_save_as(&SaveAs, window, cx),
+        }
+    }
+
+    fn on_action_save_as(&mut self, _: &SaveAs, window: &mut Window, cx: &mut Context<Self>) {
+        self.save_as(false, window, cx);
+    }
+
+    /// Ask for a path and save the document there. With `close`, the other modified files
+    /// are saved too and the window closes once nothing is left unsaved.
+    fn save_as(&mut self, close: bool, window: &mut Window, cx: &mut Context<Self>) {
+        let document = self.document.as_deref();
+        let dir = match document.and_then(Path::parent) {
+            Some(dir) => dir.to_path_buf(),
+            None => std::env::current_dir().unwrap_or_default(),
+        };
+        let name = document
+            .and_then(Path::file_name)
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "model.kdl".to_string());
+        let path = cx.prompt_for_new_path(&dir, Some(&name));
+
+        cx.spawn_in(window, async move |this, window| {
+            let path = path.await.ok()?.ok()??;
+            this.update_in(window, |this, window, cx| {
+                this.save_to(path, window, cx);
+                if close && this.document.is_some() && this.save_all(window, cx) {
+                    window.remove_window();
+                }
+            })
+            .ok();
+            Some(())
+        })
+        .detach();
+    }
+
+    /// Write the editor content to `path`, which becomes the document's location
+    fn save_to(&mut self, path: PathBuf, window: &mut Window, cx: &mut Context<Self>) {
+        let content = self.input_state.read(cx).value().to_string();
+        if !self.write_file(&path, &content, window, cx) {
+            return;
+        }
+        let path = path.canonicalize().unwrap_or(path);
+        if self.document.as_ref() != Some(&path) {
+            // Saved under a new name: the tab and, for a single document, the root follow
+            let old = self.document.replace(path.clone());
+            match self.tabs.iter().position(|tab| Some(tab) == old.as_ref()) {
+                Some(ix) => self.tabs[ix] = path.clone(),
+                None => self.tabs.push(path.clone()),
+            }
+            if self.project.is_none() && (self.root.is_none() || self.root == old) {
+                self.root = Some(path.clone());
+                self.flush_layout(cx);
+                self.layout_path = Some(layout_file::layout_path(&path));
+                self.save_layout(cx);
+            }
+            self.update_document(cx);
+        }
+        self.saved.insert(path, content);
+        self.update_edited(window, cx);
+    }
+
+    /// Save the document in the editor and all other modified files. Returns false if
+    /// something is left unsaved. An untitled document is saved under a path asked for
+    /// first, closing the window afterwards.
+    fn save_all(&mut self, window: &mut Window, cx: &mut Context<Self>) -> bool {
+        let Some(document) = self.document.clone() else {
+            self.save_as(true, window, cx);
+            return false;
+        };
+        self.save_to(document, window, cx);
+        let modified: Vec<(PathBuf, String)> = self
+            .buffers
+            .iter()
+            .filter(|(path, text)| self.is_modified(Some(*path), text))
+            .map(|(path, text)| (path.clone(), text.clone()))
+            .collect();
+        for (path, text) in modified {
+            if self.write_file(&path, &text, window, cx) {
+                self.saved.insert(path, text);
+            }
+        }
+        self.update_edited(window, cx);
+        !self.edited
+    }
+
+    fn write_file(
+        &mut self,
+        path: &Path,
+        text: &str,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> bool {
+        match std::fs::write(path, text) {
+            Ok(()) => {
+                info!("Saved {}", path.display());
+                true
+            }
+            Err(error) => {
+                error!("Cannot save {}: {}", path.display(), error);
+                let note = Notification::new().message(format!(
+                    "Cannot save {}: {}",
+                    path.display(),
+                    error
+                ));
+                window.push_notification(note, cx);
+                false
+            }
+        }
+    }
+
+    /// Whether the window may close now; otherwise ask what to do with unsaved changes
+    /// and close it afterwards
+    fn confirm_close(&mut self, window: &mut Window, cx: &mut Context<Self>) -> bool {
+        self.flush_layout(cx);
+        if !self.edited {
+            return true;
+        }
+        let answer = window.prompt(
+            PromptLevel::Warning,
+            "Save changes before closing?",
+            Some("Your changes will be lost if you don't save them."),
+            &["Save", "Don't Save", "Cancel"],
+            cx,
+        );
+        cx.spawn_in(window, async move |this, window| {
+            let answer = answer.await.ok()?;
+            this.update_in(window, |this, window, cx| {
+                let close = match answer {
+                    0 => this.save_all(window, cx),
+                    1 => true,
+                    _ => false,
+                };
+                if close {
+                    window.remove_window();
+                }
+            })
+            .ok();
+            Some(())
+        })
+        .detach();
+        false
+    }
+
+    fn on_action_close_window(
+        &mut self,
+        _: &CloseWindow,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.confirm_close(window, cx) {
+            window.remove_window();
+        }
+    }
+
+    /// Switch the editor to a file, with its unsaved edits if there are any
+    fn open_file(&mut self, path: PathBuf, window: &mut Window, cx: &mut Context<Self>) {
+        if self.document.as_ref() == Some(&path) {
+            return;
+        }
+        let text = match self.buffers.remove(&path) {
+            Some(text) => text,
+            None => match std::fs::read_to_string(&path) {
+                Ok(text) => text,
+                Err(error) => {
+                    error!("Cannot read {}: {}", path.display(), error);
+                    return;
+                }
+            },
+        };
+        self.show_file(path, text, window, cx);
+    }
+
+    /// Close a tab; edits of the file are kept. The last tab stays open.
+    fn close_tab(&mut self, path: &Path, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(ix) = self.tabs.iter().position(|tab| tab == path) else {
+            return;
+        };
+        if self.tabs.len() == 1 {
+            return;
+        }
+        self.tabs.remove(ix);
+        if self.document.as_deref() == Some(path) {
+            let next = self.tabs[ix.min(self.tabs.len() - 1)].clone();
+            self.open_file(next, window, cx);
         }
+        cx.notify();
+    }
+
+    /// Open a directory in project mode, showing its first model document
+    fn open_project(&mut self, dir: PathBuf, window: &mut Window, cx: &mut Context<Self>) {
+        self.flush_layout(cx);
+        self.layout_path = Some(layout_file::layout_path(&dir));
+        let project = cx.new(|cx| ProjectPanel::new(dir, cx));
+        self._subscriptions.push(cx.subscribe_in(
+            &project,
+            window,
+            move |this, _project, event: &OpenFile, window, cx| {
+                this.open_file(event.path.clone(), window, cx);
+            },
+        ));
+        let first = project.read(cx).files().first().cloned();
+        self.project = Some(project);
+        self.root = None;
+        self.document = None;
+        self.buffers.clear();
+        self.saved.clear();
+        self.tabs.clear();
+        self.clear_history(cx);
+        match first {
+            Some(path) => self.open_file(path, window, cx),
+            None => self.update_document(cx),
+        }
+        self.restore_layout(cx);
+    }
+
+    /// Arrange the graph as stored in the layout file, if there is one
+    fn restore_layout(&mut self, cx: &mut Context<Self>) {
+        let Some(path) = self.layout_path.as_deref().filter(|path| path.exists()) else {
+            return;
+        };
+        match layout_file::load(path) {
+            Ok(snapshot) => {
+                info!("Restoring layout from {}", path.display());
+                self.graph
+                    .update(cx, |graph, cx| graph.restore_layout(&snapshot, cx));
+                // Positions written into the model take precedence over the layout file
+                self.update_document(cx);
+            }
+            Err(error) => error!("Cannot read layout {}: {}", path.display(), error),
+        }
+    }
+
+    /// Graph node positions by node key, rounded as written into the model
+    fn node_positions(&self, cx: &App) -> HashMap<String, (f32, f32)> {
+        self.graph
+            .read(cx)
+            .nodes
+            .iter()
+            .map(|node| {
+                let node = node.read(cx);
+                let position = ((node.x / px(1.0)).round(), (node.y / px(1.0)).round());
+                (node.key.clone(), position)
+            })
+            .collect()
+    }
+
+    /// Write the positions of the nodes moved since the last projection into the model.
+    /// Like any edit, the changes stay unsaved until the files are saved.
+    fn write_layout_hints(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        // Spans of a stale graph do not match the text anymore
+        if self.graph.read(cx).stale.is_some() {
+            return;
+        }
+        let mut edits: HashMap<usize, (Option<PathBuf>, String, Vec<(usize, (f32, f32))>)> =
+            HashMap::new();
+        for node in &self.graph.read(cx).nodes {
+            let node = node.read(cx);
+            let Some((start, _)) = node.span else {
+                continue;
+            };
+            let position = ((node.x / px(1.0)).round(), (node.y / px(1.0)).round());
+            if self.placed.get(&node.key) == Some(&position) {
+                continue;
+            }
+            let Some((file, local)) = self.sources.locate(Span::new(start, start)) else {
+                continue;
+            };
+            edits
+                .entry(file.base)
+                .or_insert_with(|| (file.path.clone(), file.text.clone(), Vec::new()))
+                .2
+                .push((local.start, position));
+        }
+        if edits.is_empty() {
+            return;
+        }
+
+        for (path, text, positions) in edits.into_values() {
+            match write_positions(&text, &positions) {
+                Ok(text) => self.set_file_text(path, text, window, cx),
+                Err(error) => error!("Cannot write layout hints: {}", error),
+            }
+        }
+        self.update_document(cx);
+        self.update_edited(window, cx);
+    }
+
+    /// Current text of a model file, `None` being the document in the editor
+    fn file_text(&self, path: Option<&Path>, cx: &App) -> String {
+        match path {
+            Some(path) if Some(path) != self.document.as_deref() => match self.buffers.get(path) {
+                Some(text) => text.clone(),
+                None => std::fs::read_to_string(path).unwrap_or_default(),
+            },
+            _ => self.input_state.read(cx).value().to_string(),
+        }
+    }
+
+    /// Replace the text of a model file: in the editor if it is shown there, otherwise in
+    /// its buffer, to be saved with the other files
+    fn set_file_text(
+        &mut self,
+        path: Option<PathBuf>,
+        text: String,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        match path {
+            Some(path) if Some(&path) != self.document.as_ref() => {
+                if !self.saved.contains_key(&path) {
+                    let on_disk = std::fs::read_to_string(&path).unwrap_or_default();
+                    self.saved.insert(path.clone(), on_disk);
+                }
+                self.buffers.insert(path, text);
+            }
+            // Only the changed part is replaced, so the editor can undo it like typing
+            _ => self.input_state.update(cx, |input, cx| {
+                let old = input.value().to_string();
+                let Some(edit) = edit::diff(&old, &text) else {
+                    return;
+                };
+                let range =
+                    offset_to_utf16(&old, edit.span.start)..offset_to_utf16(&old, edit.span.end);
+                input.replace_text_in_range(Some(range), &edit.text, window, cx);
+            }),
+        }
+    }
+
+    /// Apply text edits, each given with the base offset of the file it belongs to
+    fn apply_edits(
+        &mut self,
+        edits: Vec<(usize, TextEdit)>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if edits.is_empty() {
+            return;
+        }
+        let mut by_file: HashMap<usize, Vec<TextEdit>> = HashMap::new();
+        for (base, edit) in edits {
+            by_file.entry(base).or_default().push(edit);
+        }
+        let mut changes = Vec::new();
+        for (base, edits) in by_file {
+            let Some(file) = self.sources.files.iter().find(|file| file.base == base) else {
+                continue;
+            };
+            let change = FileChange {
+                path: file.path.clone(),
+                before: file.text.clone(),
+                after: edit::apply(&file.text, edits),
+            };
+            self.set_file_text(change.path.clone(), change.after.clone(), window, cx);
+            changes.push(change);
+        }
+        self.update_document(cx);
+        self.update_edited(window, cx);
+
+        self.model_undo.push(changes);
+        self.model_redo.clear();
+        self.graph.update(cx, |graph, _| graph.push_model_edit());
+    }
+
+    /// Revert or repeat an edit made in the graph, when undo or redo in the graph reaches it
+    fn on_model_history(
+        &mut self,
+        direction: ModelHistory,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let undo = direction == ModelHistory::Undo;
+        let stack = if undo {
+            &self.model_undo
+        } else {
+            &self.model_redo
+        };
+        let Some(changes) = stack.last() else {
+            return;
+        };
+        // Typing since then would be lost, that is left to the editor's own undo. The edit
+        // stays where it is, and so does the graph's step for it.
+        let unchanged = changes.iter().all(|change| {
+            let expected = if undo { &change.after } else { &change.before };
+            self.file_text(change.path.as_deref(), cx) == *expected
+        });
+        if !unchanged {
+            self.graph
+                .update(cx, |graph, _| graph.reject_model_step(direction));
+            let note = Notification::new()
+                .message("The model was changed since this edit, undo it in the editor instead");
+            window.push_notification(note, cx);
+            return;
+        }
+        let changes = if undo {
+            self.model_undo.pop()
+        } else {
+            self.model_redo.pop()
+        };
+        let Some(changes) = changes else {
+            return;
+        };
+        for change in &changes {
+            let text = if undo { &change.before } else { &change.after };
+            self.set_file_text(change.path.clone(), text.clone(), window, cx);
+        }
+        if undo {
+            self.model_redo.push(changes);
+        } else {
+            self.model_undo.push(changes);
+        }
+        self.update_document(cx);
+        self.update_edited(window, cx);
+    }
+
+    /// Forget the undo history, when a different model is opened
+    fn clear_history(&mut self, cx: &mut Context<Self>) {
+        self.model_undo.clear();
+        self.model_redo.clear();
+        self.graph.update(cx, |graph, _| graph.clear_history());
+    }
+
+    /// Carry out a change made in the graph as an edit of the model's text
+    fn on_model_edit(&mut self, event: &ModelEdit, window: &mut Window, cx: &mut Context<Self>) {
+        // Spans of a stale graph do not match the text anymore
+        if self.graph.read(cx).stale.is_some() {
+            let note = Notification::new().message("Fix the errors in the model to edit it here");
+            window.push_notification(note, cx);
+            return;
+        }
+        let edits = match event {
+            ModelEdit::Add {
+                node_type,
+                position,
+            } => self
+                .add_edit(node_type, *position, cx)
+                .into_iter()
+                .collect(),
+            ModelEdit::Rename { span, nested, name } => {
+                let span = Span::new(span.0, span.1);
+                self.sources
+                    .locate(span)
+                    .and_then(|(file, local)| {
+                        let edit = edit::rename(&file.text, local.start, *nested, name)?;
+                        Some((file.base, edit))
+                    })
+                    .into_iter()
+                    .collect()
+            }
+            ModelEdit::Delete { spans } => spans
+                .iter()
+                .filter_map(|&(start, end)| self.sources.locate(Span::new(start, end)))
+                .map(|(file, local)| (file.base, edit::delete(&file.text, local)))
+                .collect(),
+            ModelEdit::Connect { source, target } => self
+                .connect_edit(*source, *target, cx)
+                .into_iter()
+                .collect(),
+            ModelEdit::Reparent { span, parent } => {
+                let span = Span::new(span.0, span.1);
+                let parent = Span::new(parent.0, parent.1);
+                match (self.sources.locate(span), self.sources.locate(parent)) {
+                    (Some((from, span)), Some((to, parent))) => {
+                        let element = edit::element_text(&from.text, span);
+                        vec![
+                            (from.base, edit::delete(&from.text, span)),
+                            (to.base, edit::insert_child(&to.text, parent, &element)),
+                        ]
+                    }
+                    _ => Vec::new(),
+                }
+            }
+        };
+        self.apply_edits(edits, window, cx);
+    }
+
+    /// A new top-level element at the end of the document in the editor, named after its
+    /// type and placed where it was added: `ECU1 type="ecu" x=120 y=40`
+    fn add_edit(&self, node_type: &str, (x, y): (f32, f32), cx: &App) -> Option<(usize, TextEdit)> {
+        let model = self.parse(cx).model?;
+        let taken = |name: &str| {
+            model.bus(name).is_some()
+                || model.ecu(name).is_some()
+                || model.interface(name).is_some()
+        };
+        let name = (1..)
+            .map(|n| format!("{}{}", node_type.to_uppercase(), n))
+            .find(|name| !taken(name))?;
+        let file = match &self.document {
+            Some(document) => self.sources.file(document)?,
+            None => self.sources.files.first()?,
+        };
+        let element = format!(
+            "{} type={} x={} y={}",
+            name,
+            edit::quoted(node_type),
+            x.round() as i32,
+            y.round() as i32
+        );
+        Some((file.base, edit::append(&file.text, &element)))
+    }
+
+    /// An `interface` connecting the ECU and the bus at the two spans, in either order,
+    /// after the ECU's other interfaces. `None` if they are connected already.
+    fn connect_edit(
+        &self,
+        source: (usize, usize),
+        target: (usize, usize),
+        cx: &App,
+    ) -> Option<(usize, TextEdit)> {
+        let model = self.parse(cx).model?;
+        let ecu = |span| model.ecus.iter().find(|ecu| ecu.span.range() == span);
+        let bus = |span| model.buses.iter().find(|bus| bus.span.range() == span);
+        let (ecu, bus) = match (ecu(source), bus(target)) {
+            (Some(ecu), Some(bus)) => (ecu, bus),
+            _ => (ecu(target)?, bus(source)?),
+        };
+        if ecu
+            .interfaces
+            .iter()
+            .any(|i| i.bus.as_ref() == Some(&bus.name))
+        {
+            info!("{} is already connected to {}", ecu.name, bus.name);
+            return None;
+        }
+
+        // Interfaces are numbered per kind of bus: can0, can1, eth0, ...
+        let protocol = bus.protocol.as_deref().unwrap_or_default().to_lowercase();
+        let prefix = if protocol.starts_with("can") {
+            "can"
+        } else if protocol.starts_with("lin") {
+            "lin"
+        } else if protocol.contains("base-t") || protocol.contains("ethernet") {
+            "eth"
+        } else {
+            "if"
+        };
+        let name = (0..)
+            .map(|n| format!("{}{}", prefix, n))
+            .find(|name| ecu.interfaces.iter().all(|i| &i.name != name))?;
+        let line = format!(
+            "interface {} bus={}",
+            edit::quoted(&name),
+            edit::quoted(&bus.name)
+        );
+
+        let (file, ecu_span) = self.sources.locate(ecu.span)?;
+        let edit = match ecu.interfaces.last() {
+            Some(last) => {
+                let (_, last) = self.sources.locate(last.span)?;
+                edit::insert_after(&file.text, last, &line)
+            }
+            None => edit::insert_child(&file.text, ecu_span, &line),
+        };
+        Some((file.base, edit))
+    }
+
+    fn on_action_toggle_layout_in_model(
+        &mut self,
+        _: &ToggleLayoutInModel,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.layout_in_model = !self.layout_in_model;
+        self.placed = self.node_positions(cx);
+        cx.notify();
+    }
+
+    /// Write the layout file shortly after the arrangement changed, once things settle
+    fn schedule_layout_save(&mut self, cx: &mut Context<Self>) {
+        if self.layout_path.is_none() {
+            return;
+        }
+        self.layout_save = Some(cx.spawn(async move |this, cx| {
+            cx.background_executor()
+                .timer(Duration::from_millis(500))
+                .await;
+            this.update(cx, |this, cx| {
+                this.layout_save = None;
+                this.save_layout(cx);
+            })
+            .ok();
+        }));
+    }
+
+    /// Write a pending layout change right away
+    fn flush_layout(&mut self, cx: &mut Context<Self>) {
+        if self.layout_save.take().is_some() || self.view_changed {
+            self.save_layout(cx);
+        }
+    }
+
+    fn save_layout(&mut self, cx: &mut Context<Self>) {
+        let Some(path) = &self.layout_path else {
+            return;
+        };
+        self.view_changed = false;
+        let snapshot = self.graph.read(cx).layout_snapshot(cx);
+        if let Err(error) = layout_file::save(path, &snapshot) {
+            error!("Cannot write layout {}: {}", path.display(), error);
+        }
+    }
+
+    /// Reload files changed on disk. Files without local edits are reloaded right away,
+    /// for the others the user decides whether to keep their edits.
+    fn on_files_changed(
+        &mut self,
+        paths: Vec<PathBuf>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let paths: HashSet<PathBuf> = paths.into_iter().collect();
+        let mut reloaded = false;
+        let mut conflicts = Vec::new();
+        for path in paths {
+            if !self.watcher.is_watched(&path) {
+                continue;
+            }
+            let Ok(on_disk) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            // Our own saves come back as changes, too
+            if self.saved.get(&path) == Some(&on_disk) {
+                continue;
+            }
+            let local = if self.document.as_ref() == Some(&path) {
+                Some(self.input_state.read(cx).value().to_string())
+            } else {
+                self.buffers.get(&path).cloned()
+            };
+            match local {
+                Some(local) if self.is_modified(Some(&path), &local) => {
+                    conflicts.push((path, on_disk))
+                }
+                _ => {
+                    info!("Reloading {}", path.display());
+                    self.reload_file(path, on_disk, window, cx);
+                    reloaded = true;
+                }
+            }
+        }
+        if reloaded {
+            // Node positions are kept, nodes are matched by their names
+            self.update_document(cx);
+            self.update_edited(window, cx);
+        }
+        if conflicts.is_empty() {
+            return;
+        }
+
+        let names: Vec<String> = conflicts
+            .iter()
+            .filter_map(|(path, _)| path.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .collect();
+        let answer = window.prompt(
+            PromptLevel::Warning,
+            &format!("{} changed on disk", names.join(", ")),
+            Some("The viewer has unsaved changes of it. Reload discards them."),
+            &["Reload", "Keep My Changes"],
+            cx,
+        );
+        cx.spawn_in(window, async move |this, window| {
+            let answer = answer.await.ok()?;
+            this.update_in(window, |this, window, cx| {
+                for (path, on_disk) in conflicts {
+                    if answer == 0 {
+                        this.reload_file(path, on_disk, window, cx);
+                    } else {
+                        // Compare against the new content, so the edits stay unsaved
+                        this.saved.insert(path, on_disk);
+                    }
+                }
+                this.update_document(cx);
+                this.update_edited(window, cx);
+            })
+            .ok();
+            Some(())
+        })
+        .detach();
+    }
+
+    /// Replace the loaded content of a file with `text` read from disk
+    fn reload_file(
+        &mut self,
+        path: PathBuf,
+        text: String,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.document.as_ref() == Some(&path) {
+            self.input_state.update(cx, |input, cx| {
+                input.set_value(text.clone(), window, cx);
+            });
+        } else if self.buffers.contains_key(&path) {
+            self.buffers.insert(path.clone(), text.clone());
+        }
+        self.saved.insert(path, text);
+    }
+
+    /// Re-parse the editor content and update graph and diagnostics
+    fn update_document(&mut self, cx: &mut Context<Self>) {
+        let result = self.parse(cx);
+        self.sources = result.sources.clone();
+        let files = self
+            .sources
+            .files
+            .iter()
+            .filter_map(|file| file.path.clone());
+        self.watcher.watch(files.chain(self.tabs.iter().cloned()));
+        self.show_diagnostics(&result, cx);
+
+        // Keep the last valid model on screen until the document reads again. Validation
+        // errors only show in the Problems panel, the model is still drawn.
+        let first_error = result.load_errors().min_by_key(|d| d.span.start);
+        if let Some(error) = first_error {
+            error!("Document has errors, not updating graph!");
+            let location = match self.sources.locate(error.span) {
+                Some((file, local)) => {
+                    let pos = offset_to_position(&file.text, local.start);
+                    let prefix = if file.path == self.document {
+                        String::new()
+                    } else {
+                        format!("{}:", file.name())
+                    };
+                    format!("{}{}:{}", prefix, pos.line + 1, pos.character + 1)
+                }
+                None => String::new(),
+            };
+            let stale = StaleState {
+                message: format!("{}: {}", location, error.message).into(),
+                span: Some(error.span.range()),
+            };
+            self.graph
+                .update(cx, |graph, cx| graph.set_stale(Some(stale), cx));
+            return;
+        }
+
+        if let Some(model) = result.model {
+            let (nodes, edges) = project(&model);
+            self.graph.update(cx, |graph, cx| {
+                graph.set_stale(None, cx);
+                graph.update_model(nodes, edges, cx);
+            });
+            if self.layout_in_model {
+                self.placed = self.node_positions(cx);
+            }
+        }
+    }
+
+    /// Show diagnostics as squiggles in the editor and in the problems list
+    fn show_diagnostics(&mut self, result: &ParseResult, cx: &mut Context<Self>) {
+        let sources = &result.sources;
+        let document = self.document.clone();
+        self.input_state.update(cx, |state, cx| {
+            let Some(set) = state.diagnostics_mut() else {
+                return;
+            };
+            set.clear();
+            for d in &result.diagnostics {
+                // Only diagnostics of the file in the editor get squiggles
+                let Some((file, span)) = sources.locate(d.span) else {
+                    continue;
+                };
+                if file.path != document {
+                    continue;
+                }
+                let start = offset_to_position(&file.text, span.start);
+                let end = offset_to_position(&file.text, span.end);
+                let message = match &d.hint {
+                    Some(hint) => format!("{}\n{}", d.message, hint),
+                    None => d.message.clone(),
+                };
+                let severity = match d.severity {
+                    Severity::Error => DiagnosticSeverity::Error,
+                    Severity::Warning => DiagnosticSeverity::Warning,
+                };
+                set.push(highlighter::Diagnostic::new(start..end, message).with_severity(severity));
+            }
+            cx.notify();
+        });
+        self.problems.update(cx, |problems, cx| {
+            problems.set_diagnostics(&result.diagnostics, sources, cx);
+        });
     }
 
     fn on_action_open(&mut self, _: &Open, window: &mut Window, cx: &mut Context<Self>) {
@@ -153,22 +1232,38 @@ impl Example {
             files: true,
             directories: true,
             multiple: false,
-            prompt: Some("Select a KDL file".into()),
+            prompt: Some("Select a KDL file or project directory".into()),
         });
 
-        let input_state = self.input_state.clone();
-        cx.spawn_in(window, async move |_, window| {
+        cx.spawn_in(window, async move |this, window| {
             let path = path.await.ok()?.ok()??.iter().next()?.clone();
+            let path = path.canonicalize().unwrap_or(path);
 
-            let content = std::fs::read_to_string(&path).ok()?;
-
-            window
-                .update(|window, cx| {
-                    _ = input_state.update(cx, |this, cx| {
-                        this.set_value(content, window, cx);
-                    });
+            if path.is_dir() {
+                this.update_in(window, |this, window, cx| {
+                    this.open_project(path, window, cx);
                 })
                 .ok();
+                return Some(());
+            }
+
+            let content = std::fs::read_to_string(&path).ok()?;
+
+            this.update_in(window, |this, window, cx| {
+                // The opened file becomes the root of the model
+                this.project = None;
+                this.root = Some(path.clone());
+                this.document = None;
+                this.buffers.clear();
+                this.saved.clear();
+                this.tabs.clear();
+                this.clear_history(cx);
+                this.flush_layout(cx);
+                this.layout_path = Some(layout_file::layout_path(&path));
+                this.show_file(path, content, window, cx);
+                this.restore_layout(cx);
+            })
+            .ok();
 
             Some(())
         })
@@ -178,45 +1273,132 @@ impl Example {
     fn view(window: &mut Window, cx: &mut App) -> Entity<Self> {
         cx.new(|cx| Self::new(window, cx))
     }
+
+    fn render_tabs(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let tabs = self.tabs.iter().enumerate().map(|(ix, path)| {
+            let active = self.document.as_ref() == Some(path);
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let open = path.clone();
+            let close = path.clone();
+            h_flex()
+                .id(("tab", ix))
+                .px_2()
+                .py_1()
+                .gap_2()
+                .border_r_1()
+                .border_color(cx.theme().border)
+                .cursor_pointer()
+                .when(active, |this| this.bg(cx.theme().background))
+                .when(!active, |this| this.text_color(cx.theme().muted_foreground))
+                .hover(|this| this.bg(cx.theme().secondary))
+                .child(name)
+                .child(
+                    div()
+                        .id(("close-tab", ix))
+                        .text_color(cx.theme().muted_foreground)
+                        .hover(|this| this.text_color(cx.theme().foreground))
+                        .child("×")
+                        .on_click(cx.listener(move |this, _e: &ClickEvent, window, cx| {
+                            cx.stop_propagation();
+                            this.close_tab(&close, window, cx);
+                        })),
+                )
+                .on_click(cx.listener(move |this, _e: &ClickEvent, window, cx| {
+                    this.open_file(open.clone(), window, cx);
+                }))
+        });
+
+        h_flex()
+            .id("tabs")
+            .w_full()
+            .overflow_x_scroll()
+            .bg(cx.theme().secondary)
+            .border_b_1()
+            .border_color(cx.theme().border)
+            .font_family(cx.theme().font_family.clone())
+            .text_size(px(12.0))
+            .children(tabs)
+    }
 }
 
 impl Render for Example {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let mut container = match &self.project {
+            Some(project) => h_resizable("project-container")
+                .child(resizable_panel().size(px(220.0)).child(project.clone())),
+            None => h_resizable("container"),
+        };
+        let tabs = (!self.tabs.is_empty()).then(|| self.render_tabs(cx));
+        container = container
+            .child(
+                resizable_panel().child(
+                    v_flex()
+                        .id("source")
+                        .size_full()
+                        .font_family(cx.theme().mono_font_family.clone())
+                        .text_size(cx.theme().mono_font_size)
+                        .children(tabs)
+                        .child(
+                            div().flex_1().overflow_hidden().child(
+                                Input::new(&self.input_state)
+                                    .h_full()
+                                    .p_0()
+                                    .border_0()
+                                    .focus_bordered(false),
+                            ),
+                        )
+                        .child(self.problems.clone()),
+                ),
+            )
+            .child(
+                resizable_panel().child(
+                    div()
+                        .id("graph-preview")
+                        .relative()
+                        .w_full()
+                        .h_full()
+                        .overflow_hidden()
+                        .bg(cx.theme().background)
+                        .child(self.graph.clone())
+                        .when(self.layout_in_model, |this| {
+                            this.child(
+                                div()
+                                    .id("layout-in-model")
+                                    .absolute()
+                                    .bottom_2()
+                                    .left_2()
+                                    .px_2()
+                                    .py_1()
+                                    .rounded(cx.theme().radius)
+                                    .bg(cx.theme().secondary)
+                                    .text_xs()
+                                    .text_color(cx.theme().muted_foreground)
+                                    .cursor_pointer()
+                                    .child("Writing layout to model ✕")
+                                    .on_click(cx.listener(|this, _, window, cx| {
+                                        this.on_action_toggle_layout_in_model(
+                                            &ToggleLayoutInModel,
+                                            window,
+                                            cx,
+                                        );
+                                    })),
+                            )
+                        }),
+                ),
+            );
+
         div()
             .id("editor")
             .size_full()
             .on_action(cx.listener(Self::on_action_open))
-            .child(
-                h_resizable("container")
-                    .child(
-                        resizable_panel().child(
-                            div()
-                                .id("source")
-                                .size_full()
-                                .font_family(cx.theme().mono_font_family.clone())
-                                .text_size(cx.theme().mono_font_size)
-                                .child(
-                                    Input::new(&self.input_state)
-                                        .h_full()
-                                        .p_0()
-                                        .border_0()
-                                        .focus_bordered(false),
-                                ),
-                        ),
-                    )
-                    .child(
-                        resizable_panel().child(
-                            div()
-                                .id("graph-preview")
-                                .relative()
-                                .w_full()
-                                .h_full()
-                                .overflow_hidden()
-                                .bg(cx.theme().background)
-                                .child(self.graph.clone()),
-                        ),
-                    ),
-            )
+            .on_action(cx.listener(Self::on_action_save))
+            .on_action(cx.listener(Self::on_action_save_as))
+            .on_action(cx.listener(Self::on_action_close_window))
+            .on_action(cx.listener(Self::on_action_toggle_layout_in_model))
+            .child(container)
     }
 }
 
@@ -225,6 +1407,7 @@ fn main() {
 
     app.run(move |cx| {
         gpui_component_story::init(cx);
+        graphview::init(cx);
         cx.activate(true);
 
         gpui_component_story::create_new_window_with_size(
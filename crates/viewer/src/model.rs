@@ -0,0 +1,320 @@
+//! Typed architecture model filled in by the KDL parser
+//!
+//! The model sits between the raw `kdl::KdlDocument` and the graph view. Analysis, export
+//! and validation work on these types directly, a separate projection step turns them into
+//! `GraphNode`s and `GraphEdge`s.
+
+/// Byte range of an element in the source document
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    /// Span as the `(start, end)` tuple used by `GraphNode::span`
+    pub fn range(&self) -> (usize, usize) {
+        (self.start, self.end)
+    }
+}
+
+/// The complete architecture described by one document
+#[derive(Clone, Debug, Default)]
+pub struct ArchModel {
+    pub buses: Vec<Bus>,
+    pub ecus: Vec<Ecu>,
+    pub interfaces: Vec<ServiceInterface>,
+    /// Which element kinds may be nested in which
+    pub schema: ContainmentSchema,
+}
+
+impl ArchModel {
+    pub fn bus(&self, name: &str) -> Option<&Bus> {
+        self.buses.iter().find(|b| b.name == name)
+    }
+
+    pub fn ecu(&self, name: &str) -> Option<&Ecu> {
+        self.ecus.iter().find(|e| e.name == name)
+    }
+
+    pub fn interface(&self, name: &str) -> Option<&ServiceInterface> {
+        self.interfaces.iter().find(|i| i.name == name)
+    }
+
+    /// All software components together with the ECU hosting them, at any nesting depth
+    pub fn swcs(&self) -> impl Iterator<Item = (&Ecu, &Swc)> {
+        self.ecus
+            .iter()
+            .flat_map(|ecu| ecu.swcs().into_iter().map(move |swc| (ecu, swc)))
+    }
+}
+
+/// A communication bus (`type="bus"`)
+#[derive(Clone, Debug, Default)]
+pub struct Bus {
+    pub name: String,
+    pub protocol: Option<String>,
+    pub baudrate: Option<u64>,
+    pub description: Option<String>,
+    /// Placement in the diagram, from `x=`/`y=` layout hints
+    pub position: Option<(f32, f32)>,
+    pub span: Span,
+}
+
+/// An electronic control unit (`type="ecu"`)
+#[derive(Clone, Debug, Default)]
+pub struct Ecu {
+    pub name: String,
+    pub vendor: Option<String>,
+    pub processor: Option<String>,
+    pub memory_mb: Option<u64>,
+    pub description: Option<String>,
+    pub interfaces: Vec<Interface>,
+    /// Hosted elements (hypervisors, partitions, compositions, swcs, ...)
+    pub children: Vec<Element>,
+    /// Placement in the diagram, from `x=`/`y=` layout hints
+    pub position: Option<(f32, f32)>,
+    pub span: Span,
+}
+
+impl Ecu {
+    /// Software components hosted by this ECU, depth first in document order
+    pub fn swcs(&self) -> Vec<&Swc> {
+        fn collect<'a>(elements: &'a [Element], swcs: &mut Vec<&'a Swc>) {
+            for element in elements {
+                match element {
+                    Element::Swc(swc) => swcs.push(swc),
+                    Element::Container(container) => collect(&container.children, swcs),
+                }
+            }
+        }
+        let mut swcs = Vec::new();
+        collect(&self.children, &mut swcs);
+        swcs
+    }
+}
+
+/// A network interface connecting an ECU to a bus (`interface "can0" bus="CAN_Powertrain"`)
+#[derive(Clone, Debug, Default)]
+pub struct Interface {
+    pub name: String,
+    pub bus: Option<String>,
+    /// Span of the `bus=` property, used to point diagnostics at the reference
+    pub bus_span: Option<Span>,
+    pub span: Span,
+}
+
+/// An element nested in an ECU or in another container
+#[derive(Clone, Debug)]
+pub enum Element {
+    Container(Container),
+    Swc(Swc),
+}
+
+impl Element {
+    pub fn kind(&self) -> &str {
+        match self {
+            Self::Container(container) => &container.kind,
+            Self::Swc(_) => "swc",
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Container(container) => container.span,
+            Self::Swc(swc) => swc.span,
+        }
+    }
+}
+
+/// A grouping element such as a hypervisor, vm, partition or composition
+#[derive(Clone, Debug, Default)]
+pub struct Container {
+    /// KDL node name, e.g. "partition"
+    pub kind: String,
+    pub name: String,
+    /// Simple attributes such as `safety_level "ASIL-B"`
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Element>,
+    pub span: Span,
+}
+
+/// Which element kinds may contain which, e.g. a partition may hold compositions and swcs.
+///
+/// The defaults can be overridden per kind by a top-level `schema` node:
+/// `schema { partition "composition" "swc" }`
+#[derive(Clone, Debug)]
+pub struct ContainmentSchema {
+    rules: Vec<(String, Vec<String>)>,
+}
+
+impl Default for ContainmentSchema {
+    fn default() -> Self {
+        let rule = |parent: &str, children: &[&str]| {
+            (
+                parent.to_string(),
+                children.iter().map(|c| c.to_string()).collect(),
+            )
+        };
+        Self {
+            rules: vec![
+                rule(
+                    "ecu",
+                    &["hypervisor", "vm", "partition", "composition", "swc"],
+                ),
+                rule("hypervisor", &["vm"]),
+                rule("vm", &["partition", "composition", "swc"]),
+                rule("partition", &["composition", "swc"]),
+                rule("composition", &["composition", "swc"]),
+            ],
+        }
+    }
+}
+
+impl ContainmentSchema {
+    /// Kinds allowed directly inside `parent`
+    pub fn allowed(&self, parent: &str) -> &[String] {
+        self.rules
+            .iter()
+            .find(|(kind, _)| kind == parent)
+            .map(|(_, children)| children.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn allows(&self, parent: &str, child: &str) -> bool {
+        self.allowed(parent).iter().any(|kind| kind == child)
+    }
+
+    /// Whether nodes of this kind are containment elements at all
+    pub fn is_known(&self, kind: &str) -> bool {
+        self.rules
+            .iter()
+            .any(|(parent, children)| parent == kind || children.iter().any(|c| c == kind))
+    }
+
+    /// Replace the allowed children of a kind
+    pub fn set(&mut self, parent: &str, children: Vec<String>) {
+        match self.rules.iter_mut().find(|(kind, _)| kind == parent) {
+            Some(rule) => rule.1 = children,
+            None => self.rules.push((parent.to_string(), children)),
+        }
+    }
+}
+
+/// A software component inside a partition
+#[derive(Clone, Debug, Default)]
+pub struct Swc {
+    pub name: String,
+    pub description: Option<String>,
+    pub runnables: Vec<Runnable>,
+    pub ports: Vec<Port>,
+    pub span: Span,
+}
+
+/// A runnable entity of a software component, triggered periodically or by an event
+#[derive(Clone, Debug, Default)]
+pub struct Runnable {
+    pub name: String,
+    pub period_ms: Option<u64>,
+    pub event: Option<String>,
+    pub span: Span,
+}
+
+/// Direction of a software component port
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PortDirection {
+    /// `input_port`, receives a signal
+    #[default]
+    Input,
+    /// `output_port`, sends a signal
+    Output,
+    /// `provided_port`, offers a service
+    Provided,
+    /// `required_port`, consumes a service
+    Required,
+}
+
+impl PortDirection {
+    /// Map a KDL node name to a port direction
+    pub fn from_node_name(name: &str) -> Option<Self> {
+        match name {
+            "input_port" => Some(Self::Input),
+            "output_port" => Some(Self::Output),
+            "provided_port" => Some(Self::Provided),
+            "required_port" => Some(Self::Required),
+            _ => None,
+        }
+    }
+}
+
+/// A port of a software component, either signal based or service oriented
+#[derive(Clone, Debug, Default)]
+pub struct Port {
+    pub name: String,
+    pub direction: PortDirection,
+    /// Signal carried by `input_port`/`output_port`
+    pub signal: Option<Signal>,
+    /// Service interface used by `provided_port`/`required_port`
+    pub interface: Option<String>,
+    /// Span of the `interface` node, used to point diagnostics at the reference
+    pub interface_span: Option<Span>,
+    pub protocol: Option<String>,
+    pub service_id: Option<u64>,
+    /// Span of the `service_id` node, used to point diagnostics at a conflicting id
+    pub service_id_span: Option<Span>,
+    pub methods: Vec<ServiceMember>,
+    pub events: Vec<ServiceMember>,
+    pub span: Span,
+}
+
+/// A signal referenced by a port (`signal="RPM"`)
+#[derive(Clone, Debug, Default)]
+pub struct Signal {
+    pub name: String,
+    pub span: Span,
+}
+
+/// A method or event listed on a service port
+#[derive(Clone, Debug, Default)]
+pub struct ServiceMember {
+    pub name: String,
+    pub id: Option<u64>,
+    pub span: Span,
+}
+
+/// A service interface definition (`type="interface"`)
+#[derive(Clone, Debug, Default)]
+pub struct ServiceInterface {
+    pub name: String,
+    pub description: Option<String>,
+    pub methods: Vec<Operation>,
+    pub events: Vec<Operation>,
+    /// Placement in the diagram, from `x=`/`y=` layout hints
+    pub position: Option<(f32, f32)>,
+    pub span: Span,
+}
+
+/// A method or event of a service interface
+#[derive(Clone, Debug, Default)]
+pub struct Operation {
+    pub name: String,
+    /// `input` entries of a method
+    pub inputs: Vec<TypedField>,
+    /// `output` entries of a method
+    pub outputs: Vec<TypedField>,
+    /// `field` entries of an event
+    pub fields: Vec<TypedField>,
+    pub span: Span,
+}
+
+/// A named and typed value (`output "rpm" type="uint16"`)
+#[derive(Clone, Debug, Default)]
+pub struct TypedField {
+    pub name: String,
+    pub type_name: Option<String>,
+    pub span: Span,
+}
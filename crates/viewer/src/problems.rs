@@ -0,0 +1,125 @@
+//! Problems list showing the diagnostics of the current document
+
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::model::Span;
+use crate::sources::Sources;
+use crate::text::offset_to_position;
+use gpui::*;
+use gpui_component::{ActiveTheme, h_flex, v_flex};
+use lsp_types::Position;
+
+/// Event emitted when a problem is clicked
+#[derive(Clone, Debug)]
+pub struct JumpToSource {
+    pub span: Span,
+}
+
+pub struct ProblemsPanel {
+    /// Diagnostics with the file name (empty for single-file models) and position in it
+    diagnostics: Vec<(Diagnostic, String, Position)>,
+}
+
+impl EventEmitter<JumpToSource> for ProblemsPanel {}
+
+impl ProblemsPanel {
+    pub fn new() -> Self {
+        Self {
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Replace the listed diagnostics, `sources` is used to resolve files and line numbers
+    pub fn set_diagnostics(
+        &mut self,
+        diagnostics: &[Diagnostic],
+        sources: &Sources,
+        cx: &mut Context<Self>,
+    ) {
+        let multiple_files = sources.files.len() > 1;
+        let mut diagnostics: Vec<(Diagnostic, usize, String, Position)> = diagnostics
+            .iter()
+            .map(|d| match sources.locate(d.span) {
+                Some((file, span)) => (
+                    d.clone(),
+                    file.base,
+                    if multiple_files {
+                        file.name()
+                    } else {
+                        String::new()
+                    },
+                    offset_to_position(&file.text, span.start),
+                ),
+                None => (d.clone(), 0, String::new(), Position::default()),
+            })
+            .collect();
+        diagnostics.sort_by_key(|(d, base, _, pos)| (d.severity, *base, pos.line, pos.character));
+        self.diagnostics = diagnostics
+            .into_iter()
+            .map(|(d, _, file, pos)| (d, file, pos))
+            .collect();
+        cx.notify();
+    }
+}
+
+impl Render for ProblemsPanel {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let text_color = cx.theme().foreground;
+        let muted_color = cx.theme().muted_foreground;
+        let border_color = cx.theme().border;
+
+        let rows = self
+            .diagnostics
+            .iter()
+            .enumerate()
+            .map(|(ix, (d, file, pos))| {
+                let (label, color) = match d.severity {
+                    Severity::Error => ("error", cx.theme().danger),
+                    Severity::Warning => ("warning", cx.theme().warning),
+                };
+                let span = d.span;
+                h_flex()
+                    .id(("problem", ix))
+                    .w_full()
+                    .px_2()
+                    .gap_2()
+                    .cursor_pointer()
+                    .hover(|this| this.bg(cx.theme().secondary))
+                    .child(div().text_color(color).child(label))
+                    .child(div().text_color(muted_color).child(if file.is_empty() {
+                        format!("{}:{}", pos.line + 1, pos.character + 1)
+                    } else {
+                        format!("{}:{}:{}", file, pos.line + 1, pos.character + 1)
+                    }))
+                    .child(div().text_color(text_color).child(d.message.clone()))
+                    .children(
+                        d.hint
+                            .clone()
+                            .map(|hint| div().text_color(muted_color).child(hint)),
+                    )
+                    .on_click(cx.listener(move |_this, _e: &ClickEvent, _window, cx| {
+                        cx.emit(JumpToSource { span });
+                    }))
+            });
+
+        v_flex()
+            .w_full()
+            .max_h(px(140.0))
+            .border_t_1()
+            .border_color(border_color)
+            .text_size(px(12.0))
+            .child(
+                div()
+                    .px_2()
+                    .py_1()
+                    .text_color(muted_color)
+                    .child(format!("Problems ({})", self.diagnostics.len())),
+            )
+            .child(
+                v_flex()
+                    .id("problems-list")
+                    .w_full()
+                    .overflow_y_scroll()
+                    .children(rows),
+            )
+    }
+}
@@ -0,0 +1,148 @@
+//! Project mode: a directory of KDL documents shown as a file tree
+
+use autocorrect::ignorer::Ignorer;
+use gpui::*;
+use gpui_component::{
+    ActiveTheme, IconName, h_flex,
+    list::ListItem,
+    tree::{TreeItem, TreeState, tree},
+    v_flex,
+};
+use std::path::{Path, PathBuf};
+
+/// Event emitted when a file is clicked in the tree
+#[derive(Clone, Debug)]
+pub struct OpenFile {
+    pub path: PathBuf,
+}
+
+pub struct ProjectPanel {
+    /// Project directory
+    dir: PathBuf,
+    /// All model documents below `dir`, sorted by path
+    files: Vec<PathBuf>,
+    tree_state: Entity<TreeState>,
+}
+
+impl EventEmitter<OpenFile> for ProjectPanel {}
+
+impl ProjectPanel {
+    pub fn new(dir: PathBuf, cx: &mut Context<Self>) -> Self {
+        let ignorer = Ignorer::new(&dir.to_string_lossy());
+        let mut files = Vec::new();
+        let items = build_file_items(&ignorer, &dir, &dir, &mut files);
+        files.sort();
+
+        let tree_state = cx.new(|cx| TreeState::new(cx));
+        tree_state.update(cx, |state, cx| state.set_items(items, cx));
+
+        Self {
+            dir,
+            files,
+            tree_state,
+        }
+    }
+
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+}
+
+/// Tree items for the KDL files below `path`, leaving out folders without any.
+/// The paths of all files found are added to `files`.
+fn build_file_items(
+    ignorer: &Ignorer,
+    root: &Path,
+    path: &Path,
+    files: &mut Vec<PathBuf>,
+) -> Vec<TreeItem> {
+    let mut items = Vec::new();
+    if let Ok(entries) = std::fs::read_dir(path) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let relative_path = path.strip_prefix(root).unwrap_or(&path);
+            if ignorer.is_ignored(&relative_path.to_string_lossy())
+                || relative_path.ends_with(".git")
+            {
+                continue;
+            }
+            let file_name = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("Unknown")
+                .to_string();
+            let id = path.to_string_lossy().to_string();
+            if path.is_dir() {
+                let children = build_file_items(ignorer, root, &path, files);
+                if !children.is_empty() {
+                    items.push(TreeItem::new(id, file_name).children(children));
+                }
+            } else if path.extension().is_some_and(|ext| ext == "kdl") {
+                items.push(TreeItem::new(id, file_name));
+                files.push(path.canonicalize().unwrap_or(path));
+            }
+        }
+    }
+    items.sort_by(|a, b| {
+        b.is_folder()
+            .cmp(&a.is_folder())
+            .then(a.label.cmp(&b.label))
+    });
+    items
+}
+
+impl Render for ProjectPanel {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let view = cx.entity();
+        let title = self
+            .dir
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.dir.to_string_lossy().into_owned());
+
+        v_flex()
+            .id("project")
+            .size_full()
+            .text_size(px(12.0))
+            .child(
+                div()
+                    .px_2()
+                    .py_1()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(title),
+            )
+            .child(
+                tree(
+                    &self.tree_state,
+                    move |ix, entry, _selected, _window, cx| {
+                        view.update(cx, |_, cx| {
+                            let item = entry.item();
+                            let icon = if !entry.is_folder() {
+                                IconName::File
+                            } else if entry.is_expanded() {
+                                IconName::FolderOpen
+                            } else {
+                                IconName::Folder
+                            };
+                            let is_file = !entry.is_folder();
+                            let path = PathBuf::from(item.id.to_string());
+
+                            ListItem::new(ix)
+                                .w_full()
+                                .rounded(cx.theme().radius)
+                                .px_3()
+                                .pl(px(16.) * entry.depth() + px(12.))
+                                .child(h_flex().gap_2().child(icon).child(item.label.clone()))
+                                .on_click(cx.listener(move |_this, _, _window, cx| {
+                                    if is_file {
+                                        let path = path.canonicalize().unwrap_or(path.clone());
+                                        cx.emit(OpenFile { path });
+                                    }
+                                }))
+                        })
+                    },
+                )
+                .flex_1(),
+            )
+    }
+}
@@ -0,0 +1,428 @@
+//! Projection of the typed architecture model onto graph nodes and edges
+
+use crate::connections::{service_connections, signal_connections};
+use crate::model::{
+    ArchModel, Ecu, Element, Operation, PortDirection, ServiceInterface, Span, Swc, TypedField,
+};
+use gpui::{point, px};
+use graphview::{ChildPath, EdgeKind, GraphEdge, GraphNode, NodeChild, NodePort, PortKind};
+use std::collections::{HashMap, HashSet};
+
+/// Turn the architecture model into graph nodes (buses and ECUs) with their connections
+pub fn project(model: &ArchModel) -> (Vec<GraphNode>, Vec<GraphEdge>) {
+    let mut nodes = Vec::new();
+    let mut edges = Vec::new();
+    let mut node_name_to_index: HashMap<&str, usize> = HashMap::new();
+
+    // Layout parameters for positioning nodes
+    let bus_y = 50.0f32;
+    let ecu_y = 150.0f32;
+    let row_gap = 80.0f32;
+    let start_x = 50.0f32;
+    let gap = 30.0f32;
+
+    let mut id: u64 = 1;
+
+    // Layout buses in a row with proper spacing, unless the model places them
+    let mut bus_x = start_x;
+    for bus in &model.buses {
+        node_name_to_index.insert(&bus.name, nodes.len());
+        let mut node = new_node(
+            id,
+            &bus.name,
+            "bus",
+            Vec::new(),
+            bus.span.range(),
+            bus_x,
+            bus_y,
+        );
+        node.details = details([
+            ("protocol", bus.protocol.clone()),
+            ("baudrate", bus.baudrate.map(|b| b.to_string())),
+            ("description", bus.description.clone()),
+        ]);
+        match bus.position {
+            Some(position) => place(&mut node, position),
+            None => bus_x += node.width + gap,
+        }
+        nodes.push(node);
+        id += 1;
+    }
+
+    // Layout ECUs in a row below buses with proper spacing
+    let mut ecu_x = start_x;
+    let mut ecu_height = 0.0f32;
+    for ecu in &model.ecus {
+        node_name_to_index.insert(&ecu.name, nodes.len());
+        let children = element_children(&ecu.children);
+        let mut node = new_node(
+            id,
+            &ecu.name,
+            "ecu",
+            children,
+            ecu.span.range(),
+            ecu_x,
+            ecu_y,
+        );
+        node.details = details([
+            ("vendor", ecu.vendor.clone()),
+            ("processor", ecu.processor.clone()),
+            ("memory_mb", ecu.memory_mb.map(|m| m.to_string())),
+            ("description", ecu.description.clone()),
+        ]);
+        match ecu.position {
+            Some(position) => place(&mut node, position),
+            None => {
+                ecu_x += node.width + gap;
+                ecu_height = ecu_height.max(node.height);
+            }
+        }
+        nodes.push(node);
+        id += 1;
+    }
+
+    // Layout interface definitions in a row below ECUs
+    let interface_y = ecu_y + ecu_height + row_gap;
+    let mut interface_x = start_x;
+    for interface in &model.interfaces {
+        node_name_to_index.insert(&interface.name, nodes.len());
+        let mut node = new_node(
+            id,
+            &interface.name,
+            "interface",
+            interface_members(interface),
+            interface.span.range(),
+            interface_x,
+            interface_y,
+        );
+        node.details = details([("description", interface.description.clone())]);
+        match interface.position {
+            Some(position) => place(&mut node, position),
+            None => interface_x += node.width + gap,
+        }
+        nodes.push(node);
+        id += 1;
+    }
+
+    // Connect ECUs to the buses referenced by their interfaces
+    for ecu in &model.ecus {
+        let ecu_index = node_name_to_index[ecu.name.as_str()];
+        for interface in &ecu.interfaces {
+            let Some(bus_name) = &interface.bus else {
+                continue;
+            };
+            if let Some(&bus_index) = node_name_to_index.get(bus_name.as_str()) {
+                edges.push(GraphEdge::new(ecu_index, bus_index));
+            }
+        }
+    }
+
+    let mut endpoints = Endpoints {
+        nodes: node_name_to_index,
+        swcs: HashMap::new(),
+    };
+    for ecu in &model.ecus {
+        collect_swc_paths(
+            &ecu.name,
+            &ecu.children,
+            &mut Vec::new(),
+            &mut endpoints.swcs,
+        );
+    }
+
+    // Signal data flow and services between swcs, routed through the shared bus when they
+    // cross ECUs
+    let signals = signal_connections(model);
+    let signals = signals.iter().map(|c| {
+        (
+            endpoints.swc(c.source.ecu, c.source.swc),
+            endpoints.swc(c.target.ecu, c.target.swc),
+            c.bus,
+        )
+    });
+    edges.extend(routed_edges(signals, &endpoints, EdgeKind::Signal));
+
+    let services = service_connections(model);
+    let services = services.iter().map(|c| {
+        (
+            endpoints.swc(c.provider.ecu, c.provider.swc),
+            endpoints.swc(c.consumer.ecu, c.consumer.swc),
+            c.bus,
+        )
+    });
+    edges.extend(routed_edges(services, &endpoints, EdgeKind::Service));
+
+    // Link swcs to the interface definitions their service ports reference
+    let mut references: HashSet<(Endpoint, Endpoint)> = HashSet::new();
+    for (ecu, swc) in model.swcs() {
+        for interface in swc.ports.iter().filter_map(|p| p.interface.as_deref()) {
+            if let Some(target) = endpoints.node(interface) {
+                references.insert((endpoints.swc(ecu, &swc.name), target));
+            }
+        }
+    }
+    edges.extend(sorted_edges(references, EdgeKind::Reference));
+
+    (nodes, edges)
+}
+
+/// A node index and optionally the nested child an edge attaches to
+type Endpoint = (usize, Option<ChildPath>);
+
+/// Resolves model elements to graph endpoints
+struct Endpoints<'a> {
+    nodes: HashMap<&'a str, usize>,
+    /// Child path of each swc, keyed by ECU and swc name
+    swcs: HashMap<(&'a str, &'a str), ChildPath>,
+}
+
+impl Endpoints<'_> {
+    fn node(&self, name: &str) -> Option<Endpoint> {
+        self.nodes.get(name).map(|&index| (index, None))
+    }
+
+    /// The swc box inside its ECU node (or the ECU itself if the swc is not shown)
+    fn swc(&self, ecu: &Ecu, swc: &str) -> Endpoint {
+        let path = self.swcs.get(&(ecu.name.as_str(), swc)).cloned();
+        (self.nodes[ecu.name.as_str()], path)
+    }
+}
+
+/// Record the child path of every swc below `elements`
+fn collect_swc_paths<'a>(
+    ecu: &'a str,
+    elements: &'a [Element],
+    prefix: &mut ChildPath,
+    paths: &mut HashMap<(&'a str, &'a str), ChildPath>,
+) {
+    for (index, element) in elements.iter().enumerate() {
+        prefix.push(index);
+        match element {
+            Element::Swc(swc) => {
+                paths.insert((ecu, swc.name.as_str()), prefix.clone());
+            }
+            Element::Container(container) => {
+                collect_swc_paths(ecu, &container.children, prefix, paths);
+            }
+        }
+        prefix.pop();
+    }
+}
+
+/// Edges for connections, split at the bus they are routed over.
+/// Several connections between the same endpoints share one edge.
+fn routed_edges(
+    connections: impl Iterator<Item = (Endpoint, Endpoint, Option<&str>)>,
+    endpoints: &Endpoints,
+    kind: EdgeKind,
+) -> Vec<GraphEdge> {
+    let mut pairs: HashSet<(Endpoint, Endpoint)> = HashSet::new();
+    for (source, target, bus) in connections {
+        match bus.and_then(|bus| endpoints.node(bus)) {
+            Some(bus) => {
+                pairs.insert((source, bus.clone()));
+                pairs.insert((bus, target));
+            }
+            None => {
+                pairs.insert((source, target));
+            }
+        }
+    }
+    sorted_edges(pairs, kind)
+}
+
+/// Edges in a stable order, so re-projecting an unchanged model yields identical edges
+fn sorted_edges(pairs: HashSet<(Endpoint, Endpoint)>, kind: EdgeKind) -> Vec<GraphEdge> {
+    let mut pairs: Vec<(Endpoint, Endpoint)> = pairs.into_iter().collect();
+    pairs.sort();
+    pairs
+        .into_iter()
+        .map(|((source, source_child), (target, target_child))| {
+            let mut edge = GraphEdge::new(source, target).with_kind(kind);
+            if let Some(path) = source_child {
+                edge = edge.from_child(path);
+            }
+            if let Some(path) = target_child {
+                edge = edge.to_child(path);
+            }
+            edge
+        })
+        .collect()
+}
+
+/// Nested containers and swcs shown inside an ECU node
+fn element_children(elements: &[Element]) -> Vec<NodeChild> {
+    elements
+        .iter()
+        .map(|element| match element {
+            Element::Container(container) => NodeChild {
+                name: container.name.clone(),
+                kind: container.kind.clone(),
+                children: element_children(&container.children),
+                ports: Vec::new(),
+                span: Some(container.span.range()),
+                collapsed: false,
+                details: container.attributes.clone(),
+            },
+            Element::Swc(swc) => NodeChild {
+                name: swc.name.clone(),
+                kind: "swc".to_string(),
+                children: Vec::new(),
+                ports: swc_ports(swc),
+                span: Some(swc.span.range()),
+                collapsed: false,
+                details: swc_details(swc),
+            },
+        })
+        .collect()
+}
+
+/// Signal and service ports shown as markers on a swc box
+fn swc_ports(swc: &Swc) -> Vec<NodePort> {
+    swc.ports
+        .iter()
+        .map(|port| {
+            let kind = match port.direction {
+                PortDirection::Input => PortKind::Input,
+                PortDirection::Output => PortKind::Output,
+                PortDirection::Provided => PortKind::Provided,
+                PortDirection::Required => PortKind::Required,
+            };
+            NodePort {
+                name: port.name.clone(),
+                kind,
+                signal: match &port.signal {
+                    Some(signal) => Some(signal.name.clone()),
+                    None => port.interface.clone(),
+                },
+            }
+        })
+        .collect()
+}
+
+/// Attributes that are set, as shown in a node's tooltip
+fn details<const N: usize>(attributes: [(&str, Option<String>); N]) -> Vec<(String, String)> {
+    attributes
+        .into_iter()
+        .filter_map(|(name, value)| Some((name.to_string(), value?)))
+        .collect()
+}
+
+/// Description, runnables and service details of the ports of a swc
+fn swc_details(swc: &Swc) -> Vec<(String, String)> {
+    let mut details = details([("description", swc.description.clone())]);
+    for runnable in &swc.runnables {
+        let trigger = match (runnable.period_ms, &runnable.event) {
+            (Some(period), _) => format!(" every {} ms", period),
+            (None, Some(event)) => format!(" on {}", event),
+            (None, None) => String::new(),
+        };
+        details.push((
+            "runnable".to_string(),
+            format!("{}{}", runnable.name, trigger),
+        ));
+    }
+    for port in &swc.ports {
+        let members = port.methods.iter().chain(&port.events);
+        let service = port
+            .protocol
+            .clone()
+            .into_iter()
+            .chain(port.service_id.map(|id| format!("service {}", id)))
+            .chain(members.filter_map(|m| Some(format!("{} #{}", m.name, m.id?))));
+        let service: Vec<String> = service.collect();
+        if !service.is_empty() {
+            details.push((port.name.clone(), service.join(", ")));
+        }
+    }
+    details
+}
+
+/// Methods and events listed in an interface box
+fn interface_members(interface: &ServiceInterface) -> Vec<NodeChild> {
+    let member = |kind: &str, name: String, children: Vec<NodeChild>, span: Span| NodeChild {
+        name,
+        kind: kind.to_string(),
+        children,
+        ports: Vec::new(),
+        span: Some(span.range()),
+        collapsed: false,
+        details: Vec::new(),
+    };
+    let methods = interface
+        .methods
+        .iter()
+        .map(|method| member("method", method_signature(method), Vec::new(), method.span));
+    let events = interface.events.iter().map(|event| {
+        let fields = event
+            .fields
+            .iter()
+            .map(|field| member("field", typed(field), Vec::new(), field.span))
+            .collect();
+        member("event", event.name.clone(), fields, event.span)
+    });
+    methods.chain(events).collect()
+}
+
+/// UML-like method signature, e.g. `ReadDTC(ecu_id: uint8): dtc_list: DTCList`
+fn method_signature(method: &Operation) -> String {
+    let list = |fields: &[TypedField]| fields.iter().map(typed).collect::<Vec<_>>().join(", ");
+    let outputs = match method.outputs.as_slice() {
+        [] => String::new(),
+        [output] => format!(": {}", typed(output)),
+        outputs => format!(": ({})", list(outputs)),
+    };
+    format!("{}({}){}", method.name, list(&method.inputs), outputs)
+}
+
+fn typed(field: &TypedField) -> String {
+    match &field.type_name {
+        Some(type_name) => format!("{}: {}", field.name, type_name),
+        None => field.name.clone(),
+    }
+}
+
+/// Put a node where the model's layout hints say
+fn place(node: &mut GraphNode, (x, y): (f32, f32)) {
+    node.x = px(x);
+    node.y = px(y);
+    node.hint = Some((x, y));
+}
+
+fn new_node(
+    id: u64,
+    name: &str,
+    node_type: &str,
+    children: Vec<NodeChild>,
+    span: (usize, usize),
+    x: f32,
+    y: f32,
+) -> GraphNode {
+    // Estimate node size for layout
+    let (width, height) = GraphNode::estimate_node_size(name, node_type, &children);
+    GraphNode {
+        id,
+        // Top-level KDL node names are unique, so they serve as stable identity
+        key: name.to_string(),
+        name: name.to_string(),
+        node_type: node_type.to_string(),
+        children,
+        bar_width: None,
+        x: px(x),
+        y: px(y),
+        drag_offset: None,
+        zoom: 1.0,
+        pan: point(px(0.0), px(0.0)),
+        selected: false,
+        selected_child: None,
+        collapsed: false,
+        pinned: false,
+        hint: None,
+        container_offset: point(px(0.0), px(0.0)),
+        width,
+        height,
+        span: Some(span),
+        details: Vec::new(),
+        graph_entity: None,
+    }
+}
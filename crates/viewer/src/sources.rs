@@ -0,0 +1,74 @@
+//! Source files of a model split over several documents
+//!
+//! All files share one offset space: each file starts where the previous one ended, so a
+//! `Span` identifies its file without carrying a file handle around.
+
+use crate::model::Span;
+use std::path::{Path, PathBuf};
+
+/// One document of the model
+#[derive(Clone, Debug)]
+pub struct SourceFile {
+    /// Location on disk, `None` for an unsaved buffer
+    pub path: Option<PathBuf>,
+    pub text: String,
+    /// Offset of the first byte of this file in the combined offset space
+    pub base: usize,
+}
+
+impl SourceFile {
+    /// Short name shown in messages, e.g. `powertrain.kdl`
+    pub fn name(&self) -> String {
+        self.path
+            .as_deref()
+            .and_then(Path::file_name)
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "untitled".to_string())
+    }
+
+    fn contains(&self, offset: usize) -> bool {
+        self.base <= offset && offset <= self.base + self.text.len()
+    }
+}
+
+/// All documents loaded for a model, in include order (the root document first)
+#[derive(Clone, Debug, Default)]
+pub struct Sources {
+    pub files: Vec<SourceFile>,
+}
+
+impl Sources {
+    /// Add a document and return the base offset of its spans
+    pub fn add(&mut self, path: Option<PathBuf>, text: String) -> usize {
+        // Leave a gap so the end of one file is never the start of the next
+        let base = self
+            .files
+            .last()
+            .map(|f| f.base + f.text.len() + 1)
+            .unwrap_or(0);
+        self.files.push(SourceFile { path, text, base });
+        base
+    }
+
+    pub fn file(&self, path: &Path) -> Option<&SourceFile> {
+        self.files.iter().find(|f| f.path.as_deref() == Some(path))
+    }
+
+    /// The file a span lies in, with the span relative to that file
+    pub fn locate(&self, span: Span) -> Option<(&SourceFile, Span)> {
+        let file = self.files.iter().find(|f| f.contains(span.start))?;
+        Some((
+            file,
+            Span::new(span.start - file.base, span.end - file.base),
+        ))
+    }
+
+    /// Offset in the combined space of a byte offset in the given file
+    pub fn offset_in(&self, path: Option<&Path>, offset: usize) -> Option<usize> {
+        let file = match path {
+            Some(path) => self.file(path)?,
+            None => self.files.first()?,
+        };
+        Some(file.base + offset)
+    }
+}
@@ -0,0 +1,45 @@
+//! Conversions between byte offsets and line/character positions in the editor text
+
+use lsp_types::Position;
+
+/// Convert line and character position to byte offset in the text
+pub fn line_char_to_offset(text: &str, line: usize, character: usize) -> usize {
+    let mut current_line = 0;
+    let mut current_char = 0;
+    for (i, ch) in text.char_indices() {
+        if current_line == line && current_char == character {
+            return i;
+        }
+        if ch == '\n' {
+            current_line += 1;
+            current_char = 0;
+        } else {
+            current_char += 1;
+        }
+    }
+    text.len() // if beyond the text
+}
+
+/// Convert a byte offset in the text to a line and character position
+pub fn offset_to_position(text: &str, offset: usize) -> Position {
+    let mut line = 0;
+    let mut character = 0;
+    for (i, ch) in text.char_indices() {
+        if i >= offset {
+            break;
+        }
+        if ch == '\n' {
+            line += 1;
+            character = 0;
+        } else {
+            character += 1;
+        }
+    }
+    Position::new(line, character)
+}
+
+/// Convert a byte offset in the text to an offset in UTF-16 code units, as used by the
+/// platform input handler
+pub fn offset_to_utf16(text: &str, offset: usize) -> usize {
+    text[..offset].encode_utf16().count()
+}
@@ -0,0 +1,517 @@
+//! Semantic validation of the architecture model
+
+use crate::connections::{ServiceBinding, ports, service_bindings, signal_connections};
+use crate::diagnostics::Diagnostic;
+use crate::model::{ArchModel, ContainmentSchema, Element, PortDirection, ServiceMember};
+use std::collections::{HashMap, HashSet};
+
+/// Check references and names of a syntactically valid model
+pub fn validate(model: &ArchModel) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    // Top-level names must be unique, otherwise references become ambiguous
+    let mut seen: HashSet<&str> = HashSet::new();
+    let named = model
+        .buses
+        .iter()
+        .map(|b| (b.name.as_str(), b.span))
+        .chain(model.ecus.iter().map(|e| (e.name.as_str(), e.span)))
+        .chain(model.interfaces.iter().map(|i| (i.name.as_str(), i.span)));
+    for (name, span) in named {
+        if !seen.insert(name) {
+            diagnostics.push(
+                Diagnostic::error(span, format!("`{}` is defined more than once", name))
+                    .with_hint("Rename one of the definitions"),
+            );
+        }
+    }
+
+    // Every interface must point at a declared bus
+    let bus_names: Vec<&str> = model.buses.iter().map(|b| b.name.as_str()).collect();
+    for ecu in &model.ecus {
+        for interface in &ecu.interfaces {
+            let Some(bus) = &interface.bus else {
+                diagnostics.push(
+                    Diagnostic::warning(
+                        interface.span,
+                        format!(
+                            "Interface `{}` of `{}` is not connected to a bus",
+                            interface.name, ecu.name
+                        ),
+                    )
+                    .with_hint("Add a bus=\"...\" property"),
+                );
+                continue;
+            };
+            if model.bus(bus).is_some() {
+                continue;
+            }
+            let diagnostic = Diagnostic::error(
+                interface.bus_span.unwrap_or(interface.span),
+                format!(
+                    "Interface `{}` of `{}` references unknown bus `{}`",
+                    interface.name, ecu.name, bus
+                ),
+            );
+            diagnostics.push(match closest_name(bus, &bus_names) {
+                Some(candidate) => diagnostic.with_hint(format!("Did you mean `{}`?", candidate)),
+                None => diagnostic.with_hint(format!("Declare it with `{} type=\"bus\"`", bus)),
+            });
+        }
+    }
+
+    // Signals exchanged between ECUs need a bus both of them are attached to
+    for connection in signal_connections(model) {
+        if connection.is_local() || connection.bus.is_some() {
+            continue;
+        }
+        diagnostics.push(
+            Diagnostic::warning(
+                connection.target_span(),
+                format!(
+                    "Signal `{}` from `{}.{}` reaches `{}.{}` but the ECUs share no bus",
+                    connection.signal,
+                    connection.source.ecu.name,
+                    connection.source.swc,
+                    connection.target.ecu.name,
+                    connection.target.swc
+                ),
+            )
+            .with_hint("Attach both ECUs to a common bus"),
+        );
+    }
+
+    // Service ports should reference a defined interface
+    let interface_names: Vec<&str> = model.interfaces.iter().map(|i| i.name.as_str()).collect();
+    for port in model.swcs().flat_map(|(_, swc)| &swc.ports) {
+        let Some(interface) = &port.interface else {
+            continue;
+        };
+        if model.interface(interface).is_some() {
+            continue;
+        }
+        let diagnostic = Diagnostic::warning(
+            port.interface_span.unwrap_or(port.span),
+            format!("Interface `{}` is not defined", interface),
+        );
+        diagnostics.push(match closest_name(interface, &interface_names) {
+            Some(candidate) => diagnostic.with_hint(format!("Did you mean `{}`?", candidate)),
+            None => diagnostic.with_hint(format!(
+                "Declare it with `{} type=\"interface\"`",
+                interface
+            )),
+        });
+    }
+
+    // Nesting must follow the containment schema
+    for ecu in &model.ecus {
+        validate_nesting("ecu", &ecu.children, &model.schema, &mut diagnostics);
+    }
+
+    validate_services(model, &mut diagnostics);
+
+    diagnostics
+}
+
+/// Check that each element may be placed inside its parent, recursively
+fn validate_nesting(
+    parent: &str,
+    elements: &[Element],
+    schema: &ContainmentSchema,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    for element in elements {
+        if !schema.allows(parent, element.kind()) {
+            let allowed = schema.allowed(parent);
+            let hint = if allowed.is_empty() {
+                format!("`{}` cannot contain other elements", parent)
+            } else {
+                format!("`{}` may contain: {}", parent, allowed.join(", "))
+            };
+            diagnostics.push(
+                Diagnostic::error(
+                    element.span(),
+                    format!("`{}` cannot be placed inside `{}`", element.kind(), parent),
+                )
+                .with_hint(hint),
+            );
+        }
+        if let Element::Container(container) = element {
+            validate_nesting(&container.kind, &container.children, schema, diagnostics);
+        }
+    }
+}
+
+/// Check that every `required_port` finds a provider offering what it consumes
+fn validate_services(model: &ArchModel, diagnostics: &mut Vec<Diagnostic>) {
+    // A service_id identifies one interface; two interfaces under one id cannot both be reached
+    let mut interfaces_by_id: HashMap<u64, &str> = HashMap::new();
+    for provider in ports(model, PortDirection::Provided) {
+        let (Some(id), Some(interface)) =
+            (provider.port.service_id, provider.port.interface.as_deref())
+        else {
+            continue;
+        };
+        match interfaces_by_id.get(&id) {
+            Some(other) if *other != interface => diagnostics.push(
+                Diagnostic::error(
+                    provider.port.service_id_span.unwrap_or(provider.port.span),
+                    format!(
+                        "service_id {:#06x} of `{}` is already used by interface `{}`",
+                        id, interface, other
+                    ),
+                )
+                .with_hint("Give each service interface its own service_id"),
+            ),
+            Some(_) => {}
+            None => {
+                interfaces_by_id.insert(id, interface);
+            }
+        }
+    }
+
+    for binding in service_bindings(model) {
+        match binding {
+            ServiceBinding::Connected(connection) => {
+                let (consumer, provider) = (connection.consumer.port, connection.provider.port);
+                let missing = |required: &[ServiceMember], offered: &[ServiceMember], kind| {
+                    let names: Vec<&str> = offered.iter().map(|m| m.name.as_str()).collect();
+                    required
+                        .iter()
+                        .filter(|member| !names.contains(&member.name.as_str()))
+                        .map(|member| {
+                            let diagnostic = Diagnostic::error(
+                                member.span,
+                                format!(
+                                    "`{}` does not provide {} `{}`",
+                                    connection.provider.path(),
+                                    kind,
+                                    member.name
+                                ),
+                            );
+                            match closest_name(&member.name, &names) {
+                                Some(candidate) => {
+                                    diagnostic.with_hint(format!("Did you mean `{}`?", candidate))
+                                }
+                                None => diagnostic.with_hint(format!(
+                                    "Add {} \"{}\" to the provided port",
+                                    kind, member.name
+                                )),
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                };
+                diagnostics.extend(missing(&consumer.methods, &provider.methods, "method"));
+                diagnostics.extend(missing(&consumer.events, &provider.events, "event"));
+            }
+            ServiceBinding::ServiceIdMismatch { consumer, provider } => {
+                let id = |id: Option<u64>| id.map(|id| format!("{:#06x}", id)).unwrap_or_default();
+                diagnostics.push(
+                    Diagnostic::error(
+                        consumer.port.service_id_span.unwrap_or(consumer.port.span),
+                        format!(
+                            "`{}` requires service_id {} but `{}` provides it as {}",
+                            consumer.path(),
+                            id(consumer.port.service_id),
+                            provider.path(),
+                            id(provider.port.service_id)
+                        ),
+                    )
+                    .with_hint(format!("Use service_id {}", id(provider.port.service_id))),
+                );
+            }
+            ServiceBinding::Unresolved { consumer } => {
+                let message = match &consumer.port.interface {
+                    Some(interface) => format!(
+                        "No provided_port offers `{}` required by `{}`",
+                        interface,
+                        consumer.path()
+                    ),
+                    None => format!("Required port `{}` names no interface", consumer.path()),
+                };
+                diagnostics.push(Diagnostic::warning(consumer.port.span, message));
+            }
+        }
+    }
+}
+
+/// Find the candidate with the smallest edit distance, if it is close enough to be a typo
+fn closest_name<'a>(name: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    candidates
+        .iter()
+        .map(|candidate| (edit_distance(name, candidate), *candidate))
+        .filter(|(distance, _)| *distance <= (name.len() / 3).max(2))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Levenshtein distance between two strings (case-insensitive)
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.to_lowercase().chars().collect();
+    let b: Vec<char> = b.to_lowercase().chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        current[0] = i;
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kdl::parse_model;
+
+    /// The diagnostic with `message` among those of `text`, and the source text it points at
+    fn find<'a>(text: &'a str, message: &str) -> (Diagnostic, &'a str) {
+        let model = parse_model(text).model.unwrap();
+        let diagnostics = validate(&model);
+        let Some(diagnostic) = diagnostics.iter().find(|d| d.message == message) else {
+            panic!("no diagnostic `{}` in {:#?}", message, diagnostics);
+        };
+        let span = diagnostic.span;
+        (diagnostic.clone(), text[span.start..span.end].trim())
+    }
+
+    fn hint(diagnostic: &Diagnostic) -> Option<&str> {
+        diagnostic.hint.as_deref()
+    }
+
+    #[test]
+    fn test_duplicate_name() {
+        let text = "CAN type=\"bus\"\nCAN type=\"ecu\"\n";
+        let (diagnostic, source) = find(text, "`CAN` is defined more than once");
+        assert!(diagnostic.is_error());
+        assert!(source.starts_with("CAN type=\"ecu\""));
+    }
+
+    #[test]
+    fn test_unknown_bus() {
+        let text = r#"
+            CAN type="bus"
+            ECU type="ecu" {
+                interface "can0" bus="CAM"
+                interface "eth0" bus="Ethernet"
+                interface "diag"
+            }
+        "#;
+        let (diagnostic, source) = find(
+            text,
+            "Interface `can0` of `ECU` references unknown bus `CAM`",
+        );
+        assert!(diagnostic.is_error());
+        assert_eq!(source, "bus=\"CAM\"");
+        assert_eq!(hint(&diagnostic), Some("Did you mean `CAN`?"));
+
+        let (diagnostic, _) = find(
+            text,
+            "Interface `eth0` of `ECU` references unknown bus `Ethernet`",
+        );
+        assert_eq!(
+            hint(&diagnostic),
+            Some("Declare it with `Ethernet type=\"bus\"`")
+        );
+
+        let (diagnostic, source) =
+            find(text, "Interface `diag` of `ECU` is not connected to a bus");
+        assert!(!diagnostic.is_error());
+        assert!(source.starts_with("interface \"diag\""));
+    }
+
+    #[test]
+    fn test_nesting() {
+        let text = r#"
+            ECU type="ecu" {
+                partition "P" {
+                    vm "Linux"
+                }
+            }
+        "#;
+        let (diagnostic, source) = find(text, "`vm` cannot be placed inside `partition`");
+        assert!(diagnostic.is_error());
+        assert!(source.starts_with("vm \"Linux\""));
+        assert_eq!(
+            hint(&diagnostic),
+            Some("`partition` may contain: composition, swc")
+        );
+
+        // The schema can allow it
+        let text = format!("schema {{\n    partition \"vm\"\n}}\n{}", text);
+        let model = parse_model(&text).model.unwrap();
+        assert!(validate(&model).is_empty());
+    }
+
+    #[test]
+    fn test_signal_without_shared_bus() {
+        let text = r#"
+            CAN type="bus"
+            ETH type="bus"
+            EngineECU type="ecu" {
+                interface "can0" bus="CAN"
+                swc "Engine" {
+                    output_port "RPM" signal="RPM"
+                }
+            }
+            BodyECU type="ecu" {
+                interface "eth0" bus="ETH"
+                swc "Dashboard" {
+                    input_port "RPM" signal="RPM"
+                }
+            }
+        "#;
+        let (diagnostic, source) = find(
+            text,
+            "Signal `RPM` from `EngineECU.Engine` reaches `BodyECU.Dashboard` but the ECUs share no bus",
+        );
+        assert!(!diagnostic.is_error());
+        assert_eq!(source, "signal=\"RPM\"");
+        assert!(diagnostic.span.start > text.find("Dashboard").unwrap());
+    }
+
+    #[test]
+    fn test_undefined_interface() {
+        let text = r#"
+            EngineService type="interface"
+            ECU type="ecu" {
+                swc "Engine" {
+                    provided_port "Data" {
+                        interface "EngineServce"
+                    }
+                }
+            }
+        "#;
+        let (diagnostic, source) = find(text, "Interface `EngineServce` is not defined");
+        assert!(source.starts_with("interface \"EngineServce\""));
+        assert_eq!(hint(&diagnostic), Some("Did you mean `EngineService`?"));
+    }
+
+    #[test]
+    fn test_service_id_conflict() {
+        let text = r#"
+            EngineService type="interface"
+            BrakeService type="interface"
+            ECU type="ecu" {
+                swc "Engine" {
+                    provided_port "Engine" {
+                        interface "EngineService"
+                        service_id 1
+                    }
+                    provided_port "Brake" {
+                        interface "BrakeService"
+                        service_id 1
+                    }
+                }
+            }
+        "#;
+        let (diagnostic, source) = find(
+            text,
+            "service_id 0x0001 of `BrakeService` is already used by interface `EngineService`",
+        );
+        assert!(diagnostic.is_error());
+        assert!(source.starts_with("service_id 1"));
+        assert!(diagnostic.span.start > text.find("\"Brake\"").unwrap());
+    }
+
+    #[test]
+    fn test_service_id_mismatch() {
+        let text = r#"
+            EngineService type="interface"
+            ECU type="ecu" {
+                swc "Engine" {
+                    provided_port "Data" {
+                        interface "EngineService"
+                        service_id 1
+                    }
+                }
+                swc "Dashboard" {
+                    required_port "Engine" {
+                        interface "EngineService"
+                        service_id 2
+                    }
+                }
+            }
+        "#;
+        let (diagnostic, source) = find(
+            text,
+            "`ECU.Dashboard.Engine` requires service_id 0x0002 but `ECU.Engine.Data` provides it as 0x0001",
+        );
+        assert!(diagnostic.is_error());
+        assert!(source.starts_with("service_id 2"));
+        assert_eq!(hint(&diagnostic), Some("Use service_id 0x0001"));
+    }
+
+    #[test]
+    fn test_missing_members() {
+        let text = r#"
+            EngineService type="interface"
+            ECU type="ecu" {
+                swc "Engine" {
+                    provided_port "Data" {
+                        interface "EngineService"
+                        method "GetRpm"
+                    }
+                }
+                swc "Dashboard" {
+                    required_port "Engine" {
+                        interface "EngineService"
+                        method "GetRmp"
+                        event "Stalled"
+                    }
+                }
+            }
+        "#;
+        let (diagnostic, source) = find(text, "`ECU.Engine.Data` does not provide method `GetRmp`");
+        assert!(diagnostic.is_error());
+        assert!(source.starts_with("method \"GetRmp\""));
+        assert_eq!(hint(&diagnostic), Some("Did you mean `GetRpm`?"));
+
+        let (diagnostic, source) = find(text, "`ECU.Engine.Data` does not provide event `Stalled`");
+        assert!(source.starts_with("event \"Stalled\""));
+        assert_eq!(
+            hint(&diagnostic),
+            Some("Add event \"Stalled\" to the provided port")
+        );
+    }
+
+    #[test]
+    fn test_unresolved_service() {
+        let text = r#"
+            ECU type="ecu" {
+                swc "Dashboard" {
+                    required_port "Climate" {
+                        interface "ClimateService"
+                    }
+                    required_port "Nothing"
+                }
+            }
+        "#;
+        let (diagnostic, source) = find(
+            text,
+            "No provided_port offers `ClimateService` required by `ECU.Dashboard.Climate`",
+        );
+        assert!(!diagnostic.is_error());
+        assert!(source.starts_with("required_port \"Climate\""));
+
+        let (_, source) = find(
+            text,
+            "Required port `ECU.Dashboard.Nothing` names no interface",
+        );
+        assert!(source.starts_with("required_port \"Nothing\""));
+    }
+
+    #[test]
+    fn test_closest_name() {
+        assert_eq!(closest_name("CAM", &["CAN", "ETH"]), Some("CAN"));
+        assert_eq!(closest_name("can", &["CAN"]), Some("CAN"));
+        assert_eq!(closest_name("Ethernet", &["CAN", "ETH"]), None);
+    }
+}
@@ -0,0 +1,70 @@
+//! Watching the files of the model for changes made by other programs
+
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use smol::channel::{Receiver, unbounded};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tracing::error;
+
+pub struct FileWatcher {
+    /// `None` if the platform watcher could not be created
+    watcher: Option<RecommendedWatcher>,
+    files: HashSet<PathBuf>,
+    /// Parent directories of `files`. Directories are watched rather than the files
+    /// themselves, so files replaced by editors that save via rename are still seen.
+    dirs: HashSet<PathBuf>,
+}
+
+impl FileWatcher {
+    /// Create a watcher and the channel it reports changed paths on
+    pub fn new() -> (Self, Receiver<Vec<PathBuf>>) {
+        let (sender, receiver) = unbounded();
+        let watcher =
+            notify::recommended_watcher(move |event: notify::Result<Event>| match event {
+                Ok(event) if !event.kind.is_access() => {
+                    sender.send_blocking(event.paths).ok();
+                }
+                Ok(_) => {}
+                Err(error) => error!("File watcher error: {}", error),
+            });
+        let watcher = match watcher {
+            Ok(watcher) => Some(watcher),
+            Err(error) => {
+                error!("Cannot watch files for changes: {}", error);
+                None
+            }
+        };
+        let watcher = Self {
+            watcher,
+            files: HashSet::new(),
+            dirs: HashSet::new(),
+        };
+        (watcher, receiver)
+    }
+
+    /// Watch exactly the given files from now on
+    pub fn watch(&mut self, files: impl IntoIterator<Item = PathBuf>) {
+        self.files = files.into_iter().collect();
+        let dirs: HashSet<PathBuf> = self
+            .files
+            .iter()
+            .filter_map(|file| file.parent().map(Path::to_path_buf))
+            .collect();
+        let Some(watcher) = &mut self.watcher else {
+            return;
+        };
+        for dir in self.dirs.difference(&dirs) {
+            watcher.unwatch(dir).ok();
+        }
+        for dir in dirs.difference(&self.dirs) {
+            if let Err(error) = watcher.watch(dir, RecursiveMode::NonRecursive) {
+                error!("Cannot watch {}: {}", dir.display(), error);
+            }
+        }
+        self.dirs = dirs;
+    }
+
+    pub fn is_watched(&self, path: &Path) -> bool {
+        self.files.contains(path)
+    }
+}